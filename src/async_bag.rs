@@ -0,0 +1,138 @@
+use crate::{Bag, HandlerId};
+use alloc::sync::Arc;
+use core::fmt;
+use core::future::Future;
+#[cfg(feature = "futures")]
+use futures::future::join_all;
+
+/// Data structure that holds asynchronous event handlers, that are handlers returning futures
+///
+/// Handlers are stored the same way as in [`Bag`], futures returned by them are awaited without
+/// holding any locks.
+pub struct AsyncBag<F: Send + Sync + Clone + 'static> {
+    bag: Bag<F>,
+}
+
+impl<F> fmt::Debug for AsyncBag<F>
+where
+    F: Send + Sync + Clone + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncBag").finish()
+    }
+}
+
+impl<F> Clone for AsyncBag<F>
+where
+    F: Send + Sync + Clone + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            bag: self.bag.clone(),
+        }
+    }
+}
+
+impl<F> Default for AsyncBag<F>
+where
+    F: Send + Sync + Clone + 'static,
+{
+    fn default() -> Self {
+        Self {
+            bag: Bag::default(),
+        }
+    }
+}
+
+impl<F> AsyncBag<F>
+where
+    F: Send + Sync + Clone + 'static,
+{
+    /// Add new event handler to a bag
+    pub fn add(&self, callback: F) -> HandlerId {
+        self.bag.add(callback)
+    }
+
+    /// Number of handlers currently in the bag
+    pub fn len(&self) -> usize {
+        self.bag.len()
+    }
+
+    /// Returns `true` if there are no handlers in the bag
+    pub fn is_empty(&self) -> bool {
+        self.bag.is_empty()
+    }
+
+    /// Remove handler from the bag without waiting for handler ID to be dropped.
+    ///
+    /// See [`Bag::remove()`] for details.
+    pub fn remove(&self, handler_id: &HandlerId) -> bool {
+        self.bag.remove(handler_id)
+    }
+
+    /// Remove all handlers from the bag
+    pub fn clear(&self) {
+        self.bag.clear();
+    }
+
+    /// Call applicator with each handler and await returned futures one after another, keeping
+    /// handlers in the bag
+    ///
+    /// Handlers are called in the same order as in [`Bag::call()`], next handler is called only
+    /// after future of the previous one has completed. Returns number of handlers called.
+    pub async fn call<A, Fut>(&self, mut applicator: A) -> usize
+    where
+        A: FnMut(&F) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let (handlers, _collected) = self.bag.handlers();
+        for (_, _, handler) in handlers.iter() {
+            applicator(handler).await;
+        }
+
+        handlers.len()
+    }
+
+    /// Call applicator with each handler and await returned futures concurrently, keeping
+    /// handlers in the bag
+    ///
+    /// Handlers are called in the same order as in [`Bag::call()`], returns number of handlers
+    /// called once all futures have completed.
+    #[cfg(feature = "futures")]
+    pub async fn call_concurrent<A, Fut>(&self, mut applicator: A) -> usize
+    where
+        A: FnMut(&F) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let (handlers, _collected) = self.bag.handlers();
+        join_all(handlers.iter().map(|(_, _, handler)| applicator(handler))).await;
+
+        handlers.len()
+    }
+}
+
+impl<G> AsyncBag<Arc<G>>
+where
+    G: Send + Sync + ?Sized + 'static,
+{
+    /// Call each handler without arguments and await returned futures one after another, keeping
+    /// handlers in the bag, returns number of handlers called
+    pub async fn call_async<Fut>(&self) -> usize
+    where
+        G: Fn() -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        self.call(|handler| handler()).await
+    }
+
+    /// Call each handler without arguments and await returned futures concurrently, keeping
+    /// handlers in the bag, returns number of handlers called
+    #[cfg(feature = "futures")]
+    pub async fn call_async_concurrent<Fut>(&self) -> usize
+    where
+        G: Fn() -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        self.call_concurrent(|handler| handler()).await
+    }
+}
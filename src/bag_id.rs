@@ -0,0 +1,13 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_BAG_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Process-wide unique identifier of a bag, used to check which bag handler belongs to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct BagId(usize);
+
+impl BagId {
+    pub(crate) fn new() -> Self {
+        Self(NEXT_BAG_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
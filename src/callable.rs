@@ -0,0 +1,71 @@
+use crate::{Bag, BagOnce, HandlerId};
+
+/// Common interface of [`Bag`] and [`BagOnce`] for code that should work with either of them
+///
+/// [`Bag`] keeps handlers after they are called, while [`BagOnce`] removes them.
+pub trait Callable<F> {
+    /// Add new event handler to a bag
+    fn add(&self, callback: F) -> HandlerId;
+
+    /// Call applicator with each handler in the same order as corresponding inherent method of the
+    /// bag, returns number of handlers called
+    fn call<A>(&self, applicator: A) -> usize
+    where
+        A: FnMut(F);
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> Callable<F> for Bag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Send + Sync + Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    fn add(&self, callback: F) -> HandlerId {
+        Bag::add(self, callback)
+    }
+
+    fn call<A>(&self, mut applicator: A) -> usize
+    where
+        A: FnMut(F),
+    {
+        // Handlers are already cloned while collecting, so they are given to applicator by value
+        let (handlers, collected) = self.handlers();
+        let count = handlers.len();
+        for (_, index, handler) in handlers {
+            let mut handler = Some(handler);
+            collected.invoke(index, || {
+                if let Some(handler) = handler.take() {
+                    applicator(handler);
+                }
+            });
+        }
+
+        count
+    }
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> Callable<F> for BagOnce<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Send + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    fn add(&self, callback: F) -> HandlerId {
+        BagOnce::add(self, callback)
+    }
+
+    fn call<A>(&self, applicator: A) -> usize
+    where
+        A: FnMut(F),
+    {
+        BagOnce::call(self, applicator)
+    }
+}
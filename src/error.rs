@@ -0,0 +1,51 @@
+use core::fmt;
+
+/// Error returned when handler can't be added because bag already holds maximum number of handlers
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CapacityError {
+    /// Number of handlers in the bag at the time of the attempt
+    pub current: usize,
+    /// Maximum number of handlers bag can hold
+    pub max: usize,
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Bag is full, it already holds {} handlers out of {} allowed",
+            self.current, self.max
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
+/// Error returned when handlers can't be called without blocking because bag is locked
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WouldBlock;
+
+impl fmt::Display for WouldBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bag is locked at the moment")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WouldBlock {}
+
+/// Error returned when bag stayed locked for longer than allowed timeout
+#[cfg(feature = "parking_lot")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Timeout;
+
+#[cfg(feature = "parking_lot")]
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Timed out waiting for bag to be unlocked")
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl std::error::Error for Timeout {}
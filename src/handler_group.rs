@@ -0,0 +1,67 @@
+use crate::HandlerId;
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+
+/// Group of handler IDs that are dropped together, once dropped all handlers will be removed
+/// automatically.
+///
+/// Useful for collecting handler IDs over the lifetime of a session and removing all handlers at
+/// once at its end.
+#[must_use = "Handlers will be unregistered immediately if not used"]
+#[derive(Debug, Default)]
+pub struct HandlerGroup {
+    handler_ids: Vec<HandlerId>,
+}
+
+impl Extend<HandlerId> for HandlerGroup {
+    fn extend<I: IntoIterator<Item = HandlerId>>(&mut self, iter: I) {
+        self.handler_ids.extend(iter);
+    }
+}
+
+impl FromIterator<HandlerId> for HandlerGroup {
+    fn from_iter<I: IntoIterator<Item = HandlerId>>(iter: I) -> Self {
+        Self {
+            handler_ids: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl HandlerGroup {
+    /// Create new empty group
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add handler ID to the group
+    pub fn push(&mut self, handler_id: HandlerId) {
+        self.handler_ids.push(handler_id);
+    }
+
+    /// Number of handler IDs in the group
+    pub fn len(&self) -> usize {
+        self.handler_ids.len()
+    }
+
+    /// Returns `true` if there are no handler IDs in the group
+    pub fn is_empty(&self) -> bool {
+        self.handler_ids.is_empty()
+    }
+
+    /// Prevent all handlers currently in the group from being removed automatically, like
+    /// [`HandlerId::detach()`]
+    ///
+    /// Handler IDs added to the group afterwards are not affected.
+    pub fn detach_all(&self) {
+        for handler_id in self.handler_ids.iter() {
+            handler_id.disarm();
+        }
+    }
+
+    /// Remove all handlers currently in the group immediately, like [`HandlerId::remove_now()`]
+    pub fn remove_all(&self) {
+        for handler_id in self.handler_ids.iter() {
+            handler_id.remove_now();
+        }
+    }
+}
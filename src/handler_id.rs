@@ -1,9 +1,15 @@
-use parking_lot::Mutex;
-use std::fmt;
-use std::sync::Arc;
+use crate::bag_id::BagId;
+use crate::sync::Mutex;
+use crate::HandlerKey;
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+use core::{fmt, mem};
 
 struct Inner {
     callback: Option<Box<dyn FnOnce() + Send + 'static>>,
+    children: Vec<HandlerId>,
 }
 
 impl Drop for Inner {
@@ -18,33 +24,202 @@ impl Drop for Inner {
 ///
 /// [`HandlerId::detach()`] can be used if it is not desirable for handler to be removed
 /// automatically.
+///
+/// Clones of a handler ID share the same state: handler is removed only once the last clone is
+/// dropped, dropping any other clone does nothing. Use [`HandlerId::remove_now()`] on any of the
+/// clones in order to remove handler earlier.
 #[must_use = "Handler will be unregistered immediately if not used"]
 #[derive(Clone)]
 pub struct HandlerId {
+    /// Bag ID and index of the handler, `None` for merged handler IDs
+    key: Option<(BagId, usize)>,
     inner: Arc<Mutex<Inner>>,
 }
 
 impl fmt::Debug for HandlerId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("HandlerId").finish()
+        f.debug_struct("HandlerId")
+            .field("index", &self.index())
+            .field("attached", &self.is_attached())
+            .finish()
+    }
+}
+
+// Clones share the same state, so identity of the handler ID is identity of its state
+impl PartialEq for HandlerId {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for HandlerId {}
+
+impl Hash for HandlerId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.inner).hash(state);
     }
 }
 
 impl HandlerId {
-    pub(crate) fn new<F>(f: F) -> HandlerId
+    pub(crate) fn new<F>(bag_id: BagId, index: usize, f: F) -> HandlerId
     where
         F: FnOnce() + Send + 'static,
     {
         let inner = Arc::new(Mutex::new(Inner {
             callback: Some(Box::new(f)),
+            children: Vec::new(),
+        }));
+
+        HandlerId {
+            key: Some((bag_id, index)),
+            inner,
+        }
+    }
+
+    /// Merge multiple handler IDs into one, such that all handlers are removed once merged handler
+    /// ID is dropped.
+    ///
+    /// [`HandlerId::detach()`] on merged handler ID detaches all handler IDs it was merged from.
+    pub fn merge<I>(handler_ids: I) -> HandlerId
+    where
+        I: IntoIterator<Item = HandlerId>,
+    {
+        let inner = Arc::new(Mutex::new(Inner {
+            callback: None,
+            children: handler_ids.into_iter().collect(),
         }));
 
-        HandlerId { inner }
+        HandlerId { key: None, inner }
+    }
+
+    /// Index of the handler within the bag it was added to, `None` for merged handler IDs.
+    ///
+    /// Indices are unique within a bag, but handlers in different bags may have the same index.
+    /// Indices are assigned in increasing order and wrap around after `usize::MAX`, indices of
+    /// handlers that are still in the bag are never reused. Handlers added after wraparound are
+    /// called before older handlers with the same priority.
+    ///
+    /// Handler ID stays armed when its handler is removed in bulk with
+    /// [`Bag::clear()`](crate::Bag::clear) or by key with
+    /// [`Bag::remove_key()`](crate::Bag::remove_key), so after wraparound dropping it may remove an
+    /// unrelated handler that was assigned the same index. Neither is a concern in practice since
+    /// it takes `usize::MAX` additions to happen, but long-lived handler IDs of removed handlers
+    /// should be detached.
+    pub fn index(&self) -> Option<usize> {
+        self.key.map(|(_bag_id, index)| index)
+    }
+
+    pub(crate) fn key(&self) -> Option<(BagId, usize)> {
+        self.key
+    }
+
+    /// Returns `true` if handler will be removed when handler ID is dropped, `false` if handler ID
+    /// was detached or handler was already removed with [`HandlerId::remove_now()`]
+    pub fn is_attached(&self) -> bool {
+        let inner = self.inner.lock();
+        inner.callback.is_some() || inner.children.iter().any(HandlerId::is_attached)
+    }
+
+    /// Remove handler immediately instead of waiting for handler ID to be dropped, handler ID will
+    /// do nothing when dropped afterwards
+    pub fn remove_now(&self) {
+        let (callback, children) = {
+            let mut inner = self.inner.lock();
+            (inner.callback.take(), mem::take(&mut inner.children))
+        };
+        // Callback is called without holding the lock in case it drops clones of this handler ID
+        if let Some(callback) = callback {
+            callback();
+        }
+        for child in children {
+            child.remove_now();
+        }
+    }
+
+    /// Create weak reference to handler ID that doesn't prevent handler from being removed once
+    /// all clones of handler ID are dropped
+    pub fn weak(&self) -> WeakHandlerId {
+        WeakHandlerId {
+            key: self.key,
+            inner: Arc::downgrade(&self.inner),
+        }
     }
 
     /// Consumes [`HandlerId`] and prevents handler from being removed automatically.
-    pub fn detach(&self) {
+    ///
+    /// Clones of this handler ID share the same state and will be detached as well.
+    pub fn detach(self) {
+        self.disarm();
+    }
+
+    /// Consumes [`HandlerId`] and detaches it if `condition` is `true`, otherwise this handler
+    /// ID is dropped as usual
+    ///
+    /// Handler is only removed once the last clone of the handler ID is dropped, use
+    /// [`HandlerId::remove_now()`] in order to remove it immediately.
+    ///
+    /// Handy at the end of a fallible function that registers handlers, handler IDs are dropped on
+    /// early return and handlers are kept only once `condition` is known.
+    pub fn keep_if(self, condition: bool) {
+        if condition {
+            self.detach();
+        }
+    }
+
+    /// Consumes [`HandlerId`] like [`HandlerId::detach()`] and returns key of the handler, which
+    /// can be turned back into handler ID with [`Bag::reclaim()`](crate::Bag::reclaim)
+    ///
+    /// Returns `None` for merged handler IDs, all of them are detached regardless.
+    pub fn leak(self) -> Option<HandlerKey> {
+        self.disarm();
+
+        self.key
+            .map(|(bag_id, index)| HandlerKey::new(bag_id, index))
+    }
+
+    /// Detach handler ID in place, such that it does nothing when dropped
+    pub(crate) fn disarm(&self) {
+        let mut inner = self.inner.lock();
         // Remove callback such that it is not called in drop implementation
-        self.inner.lock().callback.take();
+        inner.callback.take();
+        for child in inner.children.iter() {
+            child.disarm();
+        }
+    }
+}
+
+/// Weak reference to a [`HandlerId`], obtained with [`HandlerId::weak()`]
+///
+/// Useful for tracking handler IDs owned by someone else without extending their lifetime.
+#[derive(Clone)]
+pub struct WeakHandlerId {
+    key: Option<(BagId, usize)>,
+    inner: Weak<Mutex<Inner>>,
+}
+
+impl fmt::Debug for WeakHandlerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakHandlerId")
+            .field("index", &self.key.map(|(_bag_id, index)| index))
+            .field("alive", &self.is_alive())
+            .finish()
+    }
+}
+
+impl WeakHandlerId {
+    /// Get handler ID back if any of its clones still exists
+    pub fn upgrade(&self) -> Option<HandlerId> {
+        self.inner.upgrade().map(|inner| HandlerId {
+            key: self.key,
+            inner,
+        })
+    }
+
+    /// Returns `true` if any clone of handler ID still exists
+    ///
+    /// Handler ID might have been detached or removed with [`HandlerId::remove_now()`] already, use
+    /// [`HandlerId::is_attached()`] on upgraded handler ID to check that.
+    pub fn is_alive(&self) -> bool {
+        self.inner.strong_count() > 0
     }
 }
@@ -0,0 +1,28 @@
+use crate::bag_id::BagId;
+
+/// Key of the handler within the bag it was added to, can be copied and stored externally
+///
+/// Unlike [`HandlerId`](crate::HandlerId) key doesn't keep handler in place, it can be used to
+/// remove handler with [`Bag::remove_key()`](crate::Bag::remove_key) instead.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HandlerKey {
+    bag_id: BagId,
+    index: usize,
+}
+
+impl HandlerKey {
+    pub(crate) fn new(bag_id: BagId, index: usize) -> Self {
+        Self { bag_id, index }
+    }
+
+    /// Index of the handler within the bag it was added to, same as [`HandlerId::index()`]
+    ///
+    /// [`HandlerId::index()`]: crate::HandlerId::index
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub(crate) fn bag_id(&self) -> BagId {
+        self.bag_id
+    }
+}
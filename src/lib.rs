@@ -1,10 +1,48 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(rust_2018_idioms, missing_debug_implementations, missing_docs)]
 #![doc = include_str!("../readme.md")]
 
+extern crate alloc;
+
+#[cfg(not(any(feature = "std", feature = "spin")))]
+compile_error!("Either `std` or `spin` feature must be enabled");
+
+mod async_bag;
+mod bag_id;
+mod callable;
+mod error;
+mod handler_group;
 mod handler_id;
+mod handler_key;
+mod local;
+mod macros;
+mod map;
 mod once;
 mod regular;
+#[cfg(all(feature = "futures", feature = "std"))]
+mod stream;
+mod sync;
+mod tagged_handler_id;
 
-pub use handler_id::HandlerId;
+pub use async_bag::AsyncBag;
+pub use callable::Callable;
+#[cfg(feature = "parking_lot")]
+pub use error::Timeout;
+pub use error::{CapacityError, WouldBlock};
+pub use handler_group::HandlerGroup;
+pub use handler_id::{HandlerId, WeakHandlerId};
+pub use handler_key::HandlerKey;
+pub use local::{LocalBag, LocalBagOnce, LocalHandlerId};
 pub use once::BagOnce;
-pub use regular::Bag;
+#[cfg(feature = "serde")]
+pub use regular::IndexSnapshot;
+pub use regular::{Bag, ChangeKind, DispatchBuffer, DispatchMode, DispatchOutcome, WeakBag};
+#[cfg(all(feature = "futures", feature = "std"))]
+pub use stream::{EventStream, Overflow};
+pub use tagged_handler_id::TaggedHandlerId;
+
+/// Items used by macros, not public API
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::sync::Arc;
+}
@@ -0,0 +1,9 @@
+//! Single-threaded versions of bags that do not require handlers to be `Send` or `Sync`
+
+mod handler_id;
+mod once;
+mod regular;
+
+pub use handler_id::LocalHandlerId;
+pub use once::LocalBagOnce;
+pub use regular::LocalBag;
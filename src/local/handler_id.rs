@@ -0,0 +1,166 @@
+use crate::bag_id::BagId;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::hash::{Hash, Hasher};
+use core::{fmt, mem};
+
+struct Inner {
+    callback: Option<Box<dyn FnOnce() + 'static>>,
+    children: Vec<LocalHandlerId>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Some(callback) = self.callback.take() {
+            callback();
+        }
+    }
+}
+
+/// Single-threaded version of [`HandlerId`](crate::HandlerId) returned by
+/// [`LocalBag`](crate::LocalBag) and [`LocalBagOnce`](crate::LocalBagOnce), once dropped handler
+/// will be removed automatically.
+///
+/// [`LocalHandlerId::detach()`] can be used if it is not desirable for handler to be removed
+/// automatically.
+///
+/// Clones of a handler ID share the same state: handler is removed only once the last clone is
+/// dropped, see [`HandlerId`](crate::HandlerId) for details.
+#[must_use = "Handler will be unregistered immediately if not used"]
+#[derive(Clone)]
+pub struct LocalHandlerId {
+    /// Bag ID and index of the handler, `None` for merged handler IDs
+    key: Option<(BagId, usize)>,
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl fmt::Debug for LocalHandlerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalHandlerId")
+            .field("index", &self.index())
+            .finish()
+    }
+}
+
+// Clones share the same state, so identity of the handler ID is identity of its state
+impl PartialEq for LocalHandlerId {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for LocalHandlerId {}
+
+impl Hash for LocalHandlerId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.inner).hash(state);
+    }
+}
+
+impl LocalHandlerId {
+    pub(crate) fn new<F>(bag_id: BagId, index: usize, f: F) -> LocalHandlerId
+    where
+        F: FnOnce() + 'static,
+    {
+        let inner = Rc::new(RefCell::new(Inner {
+            callback: Some(Box::new(f)),
+            children: Vec::new(),
+        }));
+
+        LocalHandlerId {
+            key: Some((bag_id, index)),
+            inner,
+        }
+    }
+
+    /// Merge multiple handler IDs into one, such that all handlers are removed once merged handler
+    /// ID is dropped.
+    ///
+    /// [`LocalHandlerId::detach()`] on merged handler ID detaches all handler IDs it was merged
+    /// from.
+    pub fn merge<I>(handler_ids: I) -> LocalHandlerId
+    where
+        I: IntoIterator<Item = LocalHandlerId>,
+    {
+        let inner = Rc::new(RefCell::new(Inner {
+            callback: None,
+            children: handler_ids.into_iter().collect(),
+        }));
+
+        LocalHandlerId { key: None, inner }
+    }
+
+    /// Index of the handler within the bag it was added to, `None` for merged handler IDs.
+    ///
+    /// Indices are unique within a bag, but handlers in different bags may have the same index.
+    /// Indices are assigned in increasing order and wrap around after `usize::MAX`, indices of
+    /// handlers that are still in the bag are never reused.
+    ///
+    /// Handler ID stays armed when its handler is removed with
+    /// [`LocalBag::clear()`](crate::LocalBag::clear), so after wraparound dropping it may remove an
+    /// unrelated handler that was assigned the same index, which is not a concern in practice since
+    /// it takes `usize::MAX` additions to happen.
+    pub fn index(&self) -> Option<usize> {
+        self.key.map(|(_bag_id, index)| index)
+    }
+
+    pub(crate) fn key(&self) -> Option<(BagId, usize)> {
+        self.key
+    }
+
+    /// Returns `true` if handler will be removed when handler ID is dropped, `false` if handler ID
+    /// was detached or handler was already removed with [`LocalHandlerId::remove_now()`]
+    pub fn is_attached(&self) -> bool {
+        let inner = self.inner.borrow();
+        inner.callback.is_some() || inner.children.iter().any(LocalHandlerId::is_attached)
+    }
+
+    /// Remove handler immediately instead of waiting for handler ID to be dropped, handler ID will
+    /// do nothing when dropped afterwards
+    pub fn remove_now(&self) {
+        let (callback, children) = {
+            let mut inner = self.inner.borrow_mut();
+            (inner.callback.take(), mem::take(&mut inner.children))
+        };
+        // Callback is called without holding the lock in case it drops clones of this handler ID
+        if let Some(callback) = callback {
+            callback();
+        }
+        for child in children {
+            child.remove_now();
+        }
+    }
+
+    /// Consumes [`LocalHandlerId`] and prevents handler from being removed automatically.
+    ///
+    /// Clones of this handler ID share the same state and will be detached as well.
+    pub fn detach(self) {
+        self.disarm();
+    }
+
+    /// Consumes [`LocalHandlerId`] and detaches it if `condition` is `true`, otherwise this handler
+    /// ID is dropped as usual
+    ///
+    /// Handler is only removed once the last clone of the handler ID is dropped, use
+    /// [`LocalHandlerId::remove_now()`] in order to remove it immediately.
+    ///
+    /// Handy at the end of a fallible function that registers handlers, handler IDs are dropped on
+    /// early return and handlers are kept only once `condition` is known.
+    pub fn keep_if(self, condition: bool) {
+        if condition {
+            self.detach();
+        }
+    }
+
+    /// Detach handler ID in place, such that it does nothing when dropped
+    pub(crate) fn disarm(&self) {
+        let mut inner = self.inner.borrow_mut();
+        // Remove callback such that it is not called in drop implementation
+        inner.callback.take();
+        for child in inner.children.iter() {
+            child.disarm();
+        }
+    }
+}
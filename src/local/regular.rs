@@ -0,0 +1,356 @@
+use crate::bag_id::BagId;
+use crate::map::{self, Entry, Map};
+use crate::LocalHandlerId;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::fmt;
+use core::marker::PhantomData;
+use smallvec::SmallVec;
+
+mod private {
+    /// Internal type unreachable externally
+    // This struct is intentionally made `!Sized` with `[()]` such that we have no overlap with
+    // `Sized` arguments in specialized versions of `call_simple` implementations below
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    pub struct Private([()]);
+}
+
+struct Inner<F: Clone + 'static> {
+    handlers: Map<F>,
+    next_index: usize,
+}
+
+/// Single-threaded version of [`Bag`](crate::Bag) that holds `Fn()` event handlers without
+/// `Send` and `Sync` requirements
+pub struct LocalBag<
+    F: Clone + 'static,
+    A1: ?Sized = private::Private,
+    A2: ?Sized = private::Private,
+    A3: ?Sized = private::Private,
+    A4: ?Sized = private::Private,
+    A5: ?Sized = private::Private,
+    A6: ?Sized = private::Private,
+> {
+    id: BagId,
+    inner: Rc<RefCell<Inner<F>>>,
+    a1: PhantomData<A1>,
+    a2: PhantomData<A2>,
+    a3: PhantomData<A3>,
+    a4: PhantomData<A4>,
+    a5: PhantomData<A5>,
+    a6: PhantomData<A6>,
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> fmt::Debug for LocalBag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalBag").finish()
+    }
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> Clone for LocalBag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            inner: Rc::clone(&self.inner),
+            a1: PhantomData,
+            a2: PhantomData,
+            a3: PhantomData,
+            a4: PhantomData,
+            a5: PhantomData,
+            a6: PhantomData,
+        }
+    }
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> Default for LocalBag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    fn default() -> Self {
+        Self {
+            id: BagId::new(),
+            inner: Rc::new(RefCell::new(Inner {
+                handlers: Map::default(),
+                next_index: 0,
+            })),
+            a1: PhantomData,
+            a2: PhantomData,
+            a3: PhantomData,
+            a4: PhantomData,
+            a5: PhantomData,
+            a6: PhantomData,
+        }
+    }
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> LocalBag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    /// Add new event handler to a bag
+    pub fn add(&self, callback: F) -> LocalHandlerId {
+        let index;
+
+        {
+            let mut inner = self.inner.borrow_mut();
+
+            index = loop {
+                let index = inner.next_index;
+                // Index wraps around on overflow, occupied indices are skipped
+                inner.next_index = inner.next_index.wrapping_add(1);
+
+                if let Entry::Vacant(entry) = inner.handlers.entry(index) {
+                    entry.insert(callback);
+                    break index;
+                }
+            }
+        }
+
+        LocalHandlerId::new(self.id, index, {
+            let weak_inner = Rc::downgrade(&self.inner);
+
+            move || {
+                if let Some(inner) = weak_inner.upgrade() {
+                    // Handler is dropped after borrow is released, it may own handler IDs of the
+                    // same bag
+                    let handler = inner.borrow_mut().handlers.remove(&index);
+                    drop(handler);
+                }
+            }
+        })
+    }
+
+    /// Add new event handler to a bag permanently, same as `bag.add(callback).detach()`
+    pub fn add_detached(&self, callback: F) {
+        self.add(callback).detach();
+    }
+
+    /// Number of handlers currently in the bag
+    pub fn len(&self) -> usize {
+        self.inner.borrow().handlers.len()
+    }
+
+    /// Returns `true` if there are no handlers in the bag
+    pub fn is_empty(&self) -> bool {
+        self.inner.borrow().handlers.is_empty()
+    }
+
+    /// Remove handler from the bag without waiting for handler ID to be dropped.
+    ///
+    /// Returns `false` if handler ID belongs to a different bag, was merged from multiple handler
+    /// IDs or handler was already removed, handler ID is detached afterwards and will do nothing
+    /// when dropped.
+    pub fn remove(&self, handler_id: &LocalHandlerId) -> bool {
+        let index = match handler_id.key() {
+            Some((bag_id, index)) if bag_id == self.id => index,
+            _ => {
+                return false;
+            }
+        };
+
+        // Handler is dropped after borrow is released
+        let handler = self.inner.borrow_mut().handlers.remove(&index);
+        handler_id.disarm();
+
+        handler.is_some()
+    }
+
+    /// Remove all handlers from the bag
+    ///
+    /// Handler IDs of removed handlers will do nothing when dropped.
+    pub fn clear(&self) {
+        // Handlers are dropped after borrow is released
+        let handlers = map::drain(&mut self.inner.borrow_mut().handlers)
+            .collect::<SmallVec<[(usize, F); 2]>>();
+        drop(handlers);
+    }
+
+    /// Call applicator with each handler and keep handlers in the bag
+    ///
+    /// Handlers are called in the same order in which they were added to the bag, except for
+    /// handlers added after indices wrapped around (see [`LocalHandlerId::index()`]), which are
+    /// called first. Returns number of handlers called.
+    pub fn call<A>(&self, mut applicator: A) -> usize
+    where
+        A: FnMut(&F),
+    {
+        // We collect handlers first in order to avoid holding borrow while calling handlers
+        let mut handlers = self
+            .inner
+            .borrow()
+            .handlers
+            .iter()
+            .map(|(index, handler)| (*index, handler.clone()))
+            .collect::<SmallVec<[(usize, F); 2]>>();
+        // Indices are assigned in increasing order, so sorting by them restores insertion order
+        handlers.sort_unstable_by_key(|(index, _)| *index);
+        for (_, handler) in handlers.iter() {
+            applicator(handler);
+        }
+
+        handlers.len()
+    }
+
+    /// Set index to be assigned to the next handler, allows testing index wraparound without
+    /// `usize::MAX` additions
+    #[cfg(test)]
+    fn set_next_index(&self, next_index: usize) {
+        self.inner.borrow_mut().next_index = next_index;
+    }
+}
+
+impl<F: Fn() + ?Sized + 'static> LocalBag<Rc<F>> {
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self) -> usize {
+        self.call(|handler| handler())
+    }
+}
+
+impl<A1, F> LocalBag<Rc<F>, A1>
+where
+    A1: Sized,
+    F: Fn(&A1) + ?Sized + 'static,
+{
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1) -> usize {
+        self.call(|handler| handler(a1))
+    }
+}
+
+impl<A1, A2, F> LocalBag<Rc<F>, A1, A2>
+where
+    A1: Sized,
+    A2: Sized,
+    F: Fn(&A1, &A2) + ?Sized + 'static,
+{
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2) -> usize {
+        self.call(|handler| handler(a1, a2))
+    }
+}
+
+impl<A1, A2, A3, F> LocalBag<Rc<F>, A1, A2, A3>
+where
+    A1: Sized,
+    A2: Sized,
+    A3: Sized,
+    F: Fn(&A1, &A2, &A3) + ?Sized + 'static,
+{
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3) -> usize {
+        self.call(|handler| handler(a1, a2, a3))
+    }
+}
+
+impl<A1, A2, A3, A4, F> LocalBag<Rc<F>, A1, A2, A3, A4>
+where
+    A1: Sized,
+    A2: Sized,
+    A3: Sized,
+    A4: Sized,
+    F: Fn(&A1, &A2, &A3, &A4) + ?Sized + 'static,
+{
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4) -> usize {
+        self.call(|handler| handler(a1, a2, a3, a4))
+    }
+}
+
+impl<A1, A2, A3, A4, A5, F> LocalBag<Rc<F>, A1, A2, A3, A4, A5>
+where
+    A1: Sized,
+    A2: Sized,
+    A3: Sized,
+    A4: Sized,
+    A5: Sized,
+    F: Fn(&A1, &A2, &A3, &A4, &A5) + ?Sized + 'static,
+{
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5) -> usize {
+        self.call(|handler| handler(a1, a2, a3, a4, a5))
+    }
+}
+
+impl<A1, A2, A3, A4, A5, A6, F> LocalBag<Rc<F>, A1, A2, A3, A4, A5, A6>
+where
+    A1: Sized,
+    A2: Sized,
+    A3: Sized,
+    A4: Sized,
+    A5: Sized,
+    A6: Sized,
+    F: Fn(&A1, &A2, &A3, &A4, &A5, &A6) + ?Sized + 'static,
+{
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5, a6: &A6) -> usize {
+        self.call(|handler| handler(a1, a2, a3, a4, a5, a6))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn index_wraparound() {
+        let bag = LocalBag::<Rc<dyn Fn() -> usize>>::default();
+        bag.set_next_index(usize::MAX);
+
+        let first = bag.add(Rc::new(|| 1));
+        let second = bag.add(Rc::new(|| 2));
+        assert_eq!(first.index(), Some(usize::MAX));
+        assert_eq!(second.index(), Some(0));
+
+        // Indices of handlers that are still in the bag are skipped
+        bag.set_next_index(usize::MAX);
+        let third = bag.add(Rc::new(|| 3));
+        assert_eq!(third.index(), Some(1));
+
+        // Handlers added after wraparound are called first
+        let mut called = Vec::new();
+        bag.call(|handler| called.push(handler()));
+        assert_eq!(called, [2, 3, 1]);
+
+        drop(first);
+        assert_eq!(bag.len(), 2);
+    }
+}
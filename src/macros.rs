@@ -0,0 +1,96 @@
+/// Call `call_simple()` on multiple bags in the specified order, returns total number of handlers
+/// called
+///
+/// Each bag is written as a path followed by arguments of its `call_simple()` in parentheses:
+/// ```
+/// use event_listener_primitives::{dispatch, Bag};
+/// use std::sync::Arc;
+///
+/// struct Events {
+///     on_change: Bag<Arc<dyn Fn(&usize) + Send + Sync>, usize>,
+///     on_value: Bag<Arc<dyn Fn(&usize, &&str) + Send + Sync>, usize, &'static str>,
+/// }
+///
+/// let on_close = Bag::<Arc<dyn Fn() + Send + Sync>>::default();
+/// let events = Events {
+///     on_change: Bag::default(),
+///     on_value: Bag::default(),
+/// };
+/// events.on_change.add_detached(Arc::new(|_| {}));
+///
+/// assert_eq!(dispatch![events.on_change(&1), events.on_value(&1, &"a"), on_close()], 1);
+/// ```
+#[macro_export]
+macro_rules! dispatch {
+    ($($($bag:ident).+ ($($arg:expr),* $(,)?)),* $(,)?) => {
+        0_usize $(+ $($bag).+.call_simple($($arg),*))*
+    };
+}
+
+/// Define a newtype for an event backed by [`Bag`](crate::Bag) with the correct handler type
+///
+/// Generated type has `subscribe()` method that adds a closure as a handler and returns
+/// [`HandlerId`](crate::HandlerId), `emit()` method that calls all handlers with provided arguments
+/// and returns number of handlers called and `bag()` method that gives access to the underlying
+/// bag. It also implements `Debug`, `Clone` and `Default`.
+///
+/// Each argument is written as a name followed by a reference to its type, handlers receive
+/// arguments by reference.
+/// ```
+/// use event_listener_primitives::define_event;
+///
+/// define_event!(
+///     /// Emitted when value changes
+///     pub ChangeEvent, (old: &usize, new: &String)
+/// );
+/// define_event!(CloseEvent, ());
+///
+/// let on_change = ChangeEvent::default();
+/// let _handler_id = on_change.subscribe(|old, new| println!("{} -> {}", old, new));
+/// assert_eq!(on_change.emit(&1, &"a".to_string()), 1);
+///
+/// let on_close = CloseEvent::default();
+/// on_close.subscribe(|| {}).detach();
+/// assert_eq!(on_close.emit(), 1);
+/// ```
+#[macro_export]
+macro_rules! define_event {
+    (
+        $(#[$meta:meta])*
+        $vis:vis $name:ident, ($($arg:ident: &$ty:ty),* $(,)?) $(,)?
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Default)]
+        $vis struct $name(
+            $crate::Bag<
+                $crate::__private::Arc<dyn Fn($(&$ty),*) + Send + Sync + 'static>
+                $(, $ty)*
+            >,
+        );
+
+        impl $name {
+            /// Add new event handler, it will be removed when returned handler ID is dropped
+            $vis fn subscribe<F>(&self, callback: F) -> $crate::HandlerId
+            where
+                F: Fn($(&$ty),*) + Send + Sync + 'static,
+            {
+                self.0.add($crate::__private::Arc::new(callback))
+            }
+
+            /// Call each handler with provided arguments, returns number of handlers called
+            $vis fn emit(&self $(, $arg: &$ty)*) -> usize {
+                self.0.call_simple($($arg),*)
+            }
+
+            /// Underlying bag with handlers
+            $vis fn bag(
+                &self,
+            ) -> &$crate::Bag<
+                $crate::__private::Arc<dyn Fn($(&$ty),*) + Send + Sync + 'static>
+                $(, $ty)*
+            > {
+                &self.0
+            }
+        }
+    };
+}
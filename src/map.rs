@@ -0,0 +1,24 @@
+//! Storage for handlers keyed by their indices, backed by a hash map with no-op hasher when `std`
+//! feature is enabled and by `BTreeMap` otherwise
+
+#[cfg(not(feature = "std"))]
+pub(crate) use alloc::collections::btree_map::Entry;
+#[cfg(feature = "std")]
+pub(crate) use std::collections::hash_map::Entry;
+
+#[cfg(not(feature = "std"))]
+pub(crate) type Map<V> = alloc::collections::BTreeMap<usize, V>;
+#[cfg(feature = "std")]
+pub(crate) type Map<V> = nohash_hasher::IntMap<usize, V>;
+
+/// Take all handlers out of the map, retaining allocated memory where possible
+pub(crate) fn drain<V>(map: &mut Map<V>) -> impl Iterator<Item = (usize, V)> + '_ {
+    #[cfg(feature = "std")]
+    {
+        map.drain()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        core::mem::take(map).into_iter()
+    }
+}
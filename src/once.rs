@@ -1,24 +1,36 @@
+use crate::bag_id::BagId;
+use crate::map::{self, Entry, Map};
+use crate::sync::Mutex;
 use crate::HandlerId;
-use nohash_hasher::IntMap;
-use parking_lot::Mutex;
-use std::marker::PhantomData;
-use std::sync::Arc;
-use std::{fmt, mem};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use smallvec::SmallVec;
+#[cfg(feature = "std")]
+use std::any::Any;
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
 
 mod private {
     /// Internal type unreachable externally
     // This struct is intentionally made `!Sized` with `[()]` such that we have no overlap with
     // `Sized` arguments in specialized versions of `call_simple` implementations below
     #[derive(Debug)]
+    #[allow(dead_code)]
     pub struct Private([()]);
 }
 
 struct Inner<F: Send + 'static> {
-    handlers: IntMap<usize, F>,
+    handlers: Map<F>,
     next_index: usize,
 }
 
 /// Data structure that holds `FnOnce()` event handlers
+///
+/// Handlers are stored as `F` without any additional boxing, so `F` is typically `Box<T>` where `T`
+/// is `dyn FnOnce() + Send`, an already boxed handler can be passed to [`BagOnce::add()`] as is
+/// without boxing it again.
 pub struct BagOnce<
     F: Send + 'static,
     A1: ?Sized = private::Private,
@@ -26,16 +38,19 @@ pub struct BagOnce<
     A3: ?Sized = private::Private,
     A4: ?Sized = private::Private,
     A5: ?Sized = private::Private,
+    A6: ?Sized = private::Private,
 > {
+    id: BagId,
     inner: Arc<Mutex<Inner<F>>>,
     a1: PhantomData<A1>,
     a2: PhantomData<A2>,
     a3: PhantomData<A3>,
     a4: PhantomData<A4>,
     a5: PhantomData<A5>,
+    a6: PhantomData<A6>,
 }
 
-impl<F, A1, A2, A3, A4, A5> fmt::Debug for BagOnce<F, A1, A2, A3, A4, A5>
+impl<F, A1, A2, A3, A4, A5, A6> fmt::Debug for BagOnce<F, A1, A2, A3, A4, A5, A6>
 where
     F: Send + 'static,
     A1: ?Sized,
@@ -43,13 +58,27 @@ where
     A3: ?Sized,
     A4: ?Sized,
     A5: ?Sized,
+    A6: ?Sized,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("BagOnce").finish()
+        let mut debug_struct = f.debug_struct("BagOnce");
+        // Bag might be locked by the caller, `try_lock` avoids deadlock in such case
+        let indices = self.inner.try_lock().map(|inner| {
+            let mut indices = inner.handlers.keys().copied().collect::<Vec<_>>();
+            indices.sort_unstable();
+            indices
+        });
+        match indices {
+            Some(indices) => debug_struct
+                .field("handler_count", &indices.len())
+                .field("indices", &indices),
+            None => debug_struct.field("handlers", &format_args!("<locked>")),
+        };
+        debug_struct.finish()
     }
 }
 
-impl<F, A1, A2, A3, A4, A5> Clone for BagOnce<F, A1, A2, A3, A4, A5>
+impl<F, A1, A2, A3, A4, A5, A6> Clone for BagOnce<F, A1, A2, A3, A4, A5, A6>
 where
     F: Send + 'static,
     A1: ?Sized,
@@ -57,20 +86,23 @@ where
     A3: ?Sized,
     A4: ?Sized,
     A5: ?Sized,
+    A6: ?Sized,
 {
     fn clone(&self) -> Self {
         Self {
+            id: self.id,
             inner: Arc::clone(&self.inner),
-            a1: PhantomData::default(),
-            a2: PhantomData::default(),
-            a3: PhantomData::default(),
-            a4: PhantomData::default(),
-            a5: PhantomData::default(),
+            a1: PhantomData,
+            a2: PhantomData,
+            a3: PhantomData,
+            a4: PhantomData,
+            a5: PhantomData,
+            a6: PhantomData,
         }
     }
 }
 
-impl<F, A1, A2, A3, A4, A5> Default for BagOnce<F, A1, A2, A3, A4, A5>
+impl<F, A1, A2, A3, A4, A5, A6> Default for BagOnce<F, A1, A2, A3, A4, A5, A6>
 where
     F: Send + 'static,
     A1: ?Sized,
@@ -78,23 +110,14 @@ where
     A3: ?Sized,
     A4: ?Sized,
     A5: ?Sized,
+    A6: ?Sized,
 {
     fn default() -> Self {
-        Self {
-            inner: Arc::new(Mutex::new(Inner {
-                handlers: IntMap::default(),
-                next_index: 0,
-            })),
-            a1: PhantomData::default(),
-            a2: PhantomData::default(),
-            a3: PhantomData::default(),
-            a4: PhantomData::default(),
-            a5: PhantomData::default(),
-        }
+        Self::with_handlers(Map::default())
     }
 }
 
-impl<F, A1, A2, A3, A4, A5> BagOnce<F, A1, A2, A3, A4, A5>
+impl<F, A1, A2, A3, A4, A5, A6> BagOnce<F, A1, A2, A3, A4, A5, A6>
 where
     F: Send + 'static,
     A1: ?Sized,
@@ -102,7 +125,14 @@ where
     A3: ?Sized,
     A4: ?Sized,
     A5: ?Sized,
+    A6: ?Sized,
 {
+    /// Create new bag with space for at least `capacity` handlers preallocated
+    #[cfg(feature = "std")]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_handlers(Map::with_capacity_and_hasher(capacity, Default::default()))
+    }
+
     /// Add new event handler to a bag
     pub fn add(&self, callback: F) -> HandlerId {
         let index;
@@ -112,42 +142,217 @@ where
 
             index = loop {
                 let index = inner.next_index;
-                inner.next_index += 1;
+                // Index wraps around on overflow, occupied indices are skipped
+                inner.next_index = inner.next_index.wrapping_add(1);
 
-                if !inner.handlers.contains_key(&index) {
-                    inner.handlers.insert(index, callback);
+                if let Entry::Vacant(entry) = inner.handlers.entry(index) {
+                    entry.insert(callback);
                     break index;
                 }
             }
         }
 
-        HandlerId::new({
+        HandlerId::new(self.id, index, {
             let weak_inner = Arc::downgrade(&self.inner);
 
             move || {
                 if let Some(inner) = weak_inner.upgrade() {
-                    inner.lock().handlers.remove(&index);
+                    // Handler is dropped after lock is released, it may own handler IDs of the
+                    // same bag
+                    let handler = inner.lock().handlers.remove(&index);
+                    drop(handler);
                 }
             }
         })
     }
 
+    /// Add new event handler to a bag permanently, same as `bag.add(callback).detach()`
+    pub fn add_detached(&self, callback: F) {
+        self.add(callback).detach();
+    }
+
+    /// Number of handlers currently in the bag
+    pub fn len(&self) -> usize {
+        self.inner.lock().handlers.len()
+    }
+
+    /// Returns `true` if there are no handlers in the bag
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().handlers.is_empty()
+    }
+
+    /// Number of handlers the bag can hold without reallocating
+    #[cfg(feature = "std")]
+    pub fn capacity(&self) -> usize {
+        self.inner.lock().handlers.capacity()
+    }
+
+    /// Reserve space for at least `additional` more handlers to be added to the bag
+    #[cfg(feature = "std")]
+    pub fn reserve(&self, additional: usize) {
+        self.inner.lock().handlers.reserve(additional);
+    }
+
+    /// Shrink capacity of the bag as much as possible to reclaim memory after handlers were removed
+    #[cfg(feature = "std")]
+    pub fn shrink_to_fit(&self) {
+        self.inner.lock().handlers.shrink_to_fit();
+    }
+
+    /// Remove handler from the bag without waiting for handler ID to be dropped.
+    ///
+    /// Returns `false` if handler ID belongs to a different bag, was merged from multiple handler
+    /// IDs or handler was already removed, handler ID is detached afterwards and will do nothing
+    /// when dropped.
+    pub fn remove(&self, handler_id: &HandlerId) -> bool {
+        let index = match handler_id.key() {
+            Some((bag_id, index)) if bag_id == self.id => index,
+            _ => {
+                return false;
+            }
+        };
+
+        // Handler is dropped after lock is released
+        let handler = self.inner.lock().handlers.remove(&index);
+        handler_id.disarm();
+
+        handler.is_some()
+    }
+
     /// Call applicator with each handler and remove handlers from the bag
-    pub fn call<A>(&self, applicator: A)
+    ///
+    /// Handlers are called in the same order in which they were added to the bag (except for
+    /// handlers added after indices wrapped around, see [`HandlerId::index()`], which are called
+    /// first), returns number of handlers called, which is the same as number of handlers removed
+    /// from the bag.
+    ///
+    /// When called concurrently from multiple threads, each handler is called exactly once by one
+    /// of the calls, so the call that returned non-zero number is the one that fired handlers.
+    ///
+    /// Handlers are taken out of the bag before the first handler is called, so it is safe to add
+    /// new handlers from within handlers, such handlers will stay in the bag until the next call.
+    pub fn call<A>(&self, mut applicator: A) -> usize
     where
-        A: Fn(F),
+        A: FnMut(F),
     {
-        // We collect handlers first in order to avoid holding lock while calling handlers
-        let handlers = mem::take(&mut self.inner.lock().handlers);
-        for (_, handler) in handlers {
+        let handlers = self.drain();
+        let count = handlers.len();
+        for handler in handlers {
             applicator(handler);
         }
+
+        count
+    }
+
+    /// Call applicator with each handler, catching panics, and remove handlers from the bag
+    ///
+    /// All handlers are called even if some of them panic, returns panic payloads in the order
+    /// handlers were called. Applicator and handlers are assumed to be unwind safe, see
+    /// [`std::panic::catch_unwind()`] for details.
+    #[cfg(feature = "std")]
+    pub fn call_catch_unwind<A>(&self, mut applicator: A) -> Vec<Box<dyn Any + Send>>
+    where
+        A: FnMut(F),
+    {
+        let mut panics = Vec::new();
+        self.call(|handler| {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| applicator(handler))) {
+                panics.push(payload);
+            }
+        });
+
+        panics
+    }
+
+    /// Remove all handlers from the bag and return them in the same order as in
+    /// [`BagOnce::call()`]
+    ///
+    /// Unlike [`BagOnce::call()`] it is up to the caller to decide what to do with handlers, those
+    /// that are not consumed are dropped together with the iterator. Handler IDs of removed
+    /// handlers will do nothing when dropped.
+    pub fn drain(&self) -> impl ExactSizeIterator<Item = F> {
+        self.take_handlers()
+            .into_iter()
+            .map(|(_index, handler)| handler)
+    }
+
+    /// Call applicator with each handler and remove handlers from the bag, returns indices of
+    /// handlers called in the order they were called
+    ///
+    /// Same as [`BagOnce::call()`], but indices allow cleaning up external data keyed by
+    /// [`HandlerId::index()`] without keeping handler IDs around.
+    pub fn call_reporting<A>(&self, mut applicator: A) -> Vec<usize>
+    where
+        A: FnMut(F),
+    {
+        self.take_handlers()
+            .into_iter()
+            .map(|(index, handler)| {
+                applicator(handler);
+                index
+            })
+            .collect()
+    }
+
+    /// Remove handlers from the bag and put them into `out` in the same order as in
+    /// [`BagOnce::call()`] without calling them, returns number of handlers removed
+    ///
+    /// `out` is cleared first, which allows reusing its allocation across calls. Handler IDs of
+    /// removed handlers will do nothing when dropped.
+    pub fn call_into(&self, out: &mut Vec<F>) -> usize {
+        out.clear();
+        out.extend(self.drain());
+
+        out.len()
+    }
+
+    /// Call applicator with each handler, remove handlers from the bag and collect applicator
+    /// results in the order handlers were called
+    pub fn call_collect<R, A>(&self, mut applicator: A) -> Vec<R>
+    where
+        A: FnMut(F) -> R,
+    {
+        let mut results = Vec::new();
+        self.call(|handler| {
+            results.push(applicator(handler));
+        });
+
+        results
+    }
+
+    /// Take all handlers out of the bag with their indices in insertion order
+    fn take_handlers(&self) -> SmallVec<[(usize, F); 2]> {
+        // We collect handlers first in order to avoid holding lock while calling handlers
+        // Draining instead of taking the whole map retains allocated capacity for the next handlers
+        let mut handlers =
+            map::drain(&mut self.inner.lock().handlers).collect::<SmallVec<[(usize, F); 2]>>();
+        // Indices are assigned in increasing order, so sorting by them restores insertion order
+        handlers.sort_unstable_by_key(|(index, _)| *index);
+
+        handlers
+    }
+
+    fn with_handlers(handlers: Map<F>) -> Self {
+        Self {
+            id: BagId::new(),
+            inner: Arc::new(Mutex::new(Inner {
+                handlers,
+                next_index: 0,
+            })),
+            a1: PhantomData,
+            a2: PhantomData,
+            a3: PhantomData,
+            a4: PhantomData,
+            a5: PhantomData,
+            a6: PhantomData,
+        }
     }
 }
 
 impl<F: FnOnce() + Send + 'static> BagOnce<F> {
-    /// Call each handler without arguments and remove handlers from the bag
-    pub fn call_simple(&self) {
+    /// Call each handler without arguments and remove handlers from the bag, returns number of
+    /// handlers called
+    pub fn call_simple(&self) -> usize {
         self.call(|handler| handler())
     }
 }
@@ -157,8 +362,9 @@ where
     A1: Sized,
     F: FnOnce(&A1) + Send + 'static,
 {
-    /// Call each handler without arguments and remove handlers from the bag
-    pub fn call_simple(&self, a1: &A1) {
+    /// Call each handler without arguments and remove handlers from the bag, returns number of
+    /// handlers called
+    pub fn call_simple(&self, a1: &A1) -> usize {
         self.call(|handler| handler(a1))
     }
 }
@@ -169,8 +375,9 @@ where
     A2: Sized,
     F: FnOnce(&A1, &A2) + Send + 'static,
 {
-    /// Call each handler without arguments and remove handlers from the bag
-    pub fn call_simple(&self, a1: &A1, a2: &A2) {
+    /// Call each handler without arguments and remove handlers from the bag, returns number of
+    /// handlers called
+    pub fn call_simple(&self, a1: &A1, a2: &A2) -> usize {
         self.call(|handler| handler(a1, a2))
     }
 }
@@ -182,8 +389,9 @@ where
     A3: Sized,
     F: FnOnce(&A1, &A2, &A3) + Send + 'static,
 {
-    /// Call each handler without arguments and remove handlers from the bag
-    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3) {
+    /// Call each handler without arguments and remove handlers from the bag, returns number of
+    /// handlers called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3) -> usize {
         self.call(|handler| handler(a1, a2, a3))
     }
 }
@@ -196,8 +404,9 @@ where
     A4: Sized,
     F: FnOnce(&A1, &A2, &A3, &A4) + Send + 'static,
 {
-    /// Call each handler without arguments and remove handlers from the bag
-    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4) {
+    /// Call each handler without arguments and remove handlers from the bag, returns number of
+    /// handlers called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4) -> usize {
         self.call(|handler| handler(a1, a2, a3, a4))
     }
 }
@@ -211,8 +420,122 @@ where
     A5: Sized,
     F: FnOnce(&A1, &A2, &A3, &A4, &A5) + Send + 'static,
 {
-    /// Call each handler without arguments and remove handlers from the bag
-    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5) {
+    /// Call each handler without arguments and remove handlers from the bag, returns number of
+    /// handlers called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5) -> usize {
         self.call(|handler| handler(a1, a2, a3, a4, a5))
     }
 }
+
+impl<A1, A2, A3, A4, A5, A6, F> BagOnce<F, A1, A2, A3, A4, A5, A6>
+where
+    A1: Sized,
+    A2: Sized,
+    A3: Sized,
+    A4: Sized,
+    A5: Sized,
+    A6: Sized,
+    F: FnOnce(&A1, &A2, &A3, &A4, &A5, &A6) + Send + 'static,
+{
+    /// Call each handler without arguments and remove handlers from the bag, returns number of
+    /// handlers called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5, a6: &A6) -> usize {
+        self.call(|handler| handler(a1, a2, a3, a4, a5, a6))
+    }
+}
+
+impl<A1, F> BagOnce<F, A1>
+where
+    A1: Sized + Clone,
+    F: FnOnce(A1) + Send + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and remove handlers from the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1) -> usize {
+        self.call(|handler| handler(a1.clone()))
+    }
+}
+
+impl<A1, A2, F> BagOnce<F, A1, A2>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    F: FnOnce(A1, A2) + Send + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and remove handlers from the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2) -> usize {
+        self.call(|handler| handler(a1.clone(), a2.clone()))
+    }
+}
+
+impl<A1, A2, A3, F> BagOnce<F, A1, A2, A3>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    A3: Sized + Clone,
+    F: FnOnce(A1, A2, A3) + Send + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and remove handlers from the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2, a3: A3) -> usize {
+        self.call(|handler| handler(a1.clone(), a2.clone(), a3.clone()))
+    }
+}
+
+impl<A1, A2, A3, A4, F> BagOnce<F, A1, A2, A3, A4>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    A3: Sized + Clone,
+    A4: Sized + Clone,
+    F: FnOnce(A1, A2, A3, A4) + Send + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and remove handlers from the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2, a3: A3, a4: A4) -> usize {
+        self.call(|handler| handler(a1.clone(), a2.clone(), a3.clone(), a4.clone()))
+    }
+}
+
+impl<A1, A2, A3, A4, A5, F> BagOnce<F, A1, A2, A3, A4, A5>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    A3: Sized + Clone,
+    A4: Sized + Clone,
+    A5: Sized + Clone,
+    F: FnOnce(A1, A2, A3, A4, A5) + Send + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and remove handlers from the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2, a3: A3, a4: A4, a5: A5) -> usize {
+        self.call(|handler| handler(a1.clone(), a2.clone(), a3.clone(), a4.clone(), a5.clone()))
+    }
+}
+
+impl<A1, A2, A3, A4, A5, A6, F> BagOnce<F, A1, A2, A3, A4, A5, A6>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    A3: Sized + Clone,
+    A4: Sized + Clone,
+    A5: Sized + Clone,
+    A6: Sized + Clone,
+    F: FnOnce(A1, A2, A3, A4, A5, A6) + Send + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and remove handlers from the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2, a3: A3, a4: A4, a5: A5, a6: A6) -> usize {
+        self.call(|handler| {
+            handler(
+                a1.clone(),
+                a2.clone(),
+                a3.clone(),
+                a4.clone(),
+                a5.clone(),
+                a6.clone(),
+            )
+        })
+    }
+}
@@ -1,25 +1,276 @@
-use crate::HandlerId;
-use nohash_hasher::IntMap;
-use parking_lot::Mutex;
+use crate::bag_id::BagId;
+use crate::map::{self, Entry, Map};
+use crate::sync::{Mutex, RwLock};
+#[cfg(feature = "parking_lot")]
+use crate::Timeout;
+use crate::{CapacityError, HandlerId, HandlerKey, TaggedHandlerId, WouldBlock};
+#[cfg(all(feature = "futures", feature = "std"))]
+use crate::{EventStream, Overflow};
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt;
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::ControlFlow;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
-use std::fmt;
-use std::marker::PhantomData;
-use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::any::Any;
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "std")]
+use std::sync::mpsc;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
 mod private {
     /// Internal type unreachable externally
     // This struct is intentionally made `!Sized` with `[()]` such that we have no overlap with
-    // `Sized` arguments in specialized versions of `call_simple` implementations below
+    // `Sized` arguments in specialized versions of `call_simple` implementations below. This is
+    // also why those arguments can't be `?Sized`: `Bag<Arc<F>, A1>` with `A1 = Private` would be
+    // the same type as `Bag<Arc<F>>` and `call_simple` would be defined twice for it.
     #[derive(Debug)]
+    #[allow(dead_code)]
     pub struct Private([()]);
 }
 
 struct Inner<F: Send + Sync + Clone + 'static> {
-    handlers: IntMap<usize, F>,
+    // Indices are never reused (until `usize` wraps around), so the index itself acts as a
+    // generation and a stale handler ID can't remove a handler that was added later. Map with no-op
+    // hasher keeps both insertion and removal cheap, while a slab with reused slots would require
+    // separate generations and would lose insertion order that we restore by sorting on index.
+    // Handlers are stored together with their priority.
+    handlers: Map<(i32, F)>,
     next_index: usize,
+    limit: Option<usize>,
+    on_change: Option<ChangeHook>,
+    /// Label for diagnostics
+    name: Option<&'static str>,
+    middleware: Option<Middleware>,
+    /// Starting position for the next [`Bag::call_rotating()`]
+    cursor: AtomicUsize,
+    /// Incremented every time set of handlers changes
+    #[cfg(feature = "debug-checks")]
+    version: u64,
+    #[cfg(feature = "debug-checks")]
+    on_change_during_call: Option<Arc<dyn Fn() + Send + Sync + 'static>>,
+}
+
+impl<F: Send + Sync + Clone + 'static> Inner<F> {
+    /// Append clones of handlers with their priorities and indices to `handlers` in arbitrary order
+    fn clone_handlers_into(&self, handlers: &mut SmallVec<[(i32, usize, F); 2]>) {
+        handlers.extend(
+            self.handlers
+                .iter()
+                .map(|(index, (priority, handler))| (*priority, *index, handler.clone())),
+        );
+    }
+
+    /// Insert handler and return its index
+    fn insert(&mut self, callback: F, priority: i32) -> usize {
+        self.mark_changed();
+        loop {
+            let index = self.next_index;
+            // Index wraps around on overflow, occupied indices are skipped
+            self.next_index = self.next_index.wrapping_add(1);
+
+            if let Entry::Vacant(entry) = self.handlers.entry(index) {
+                entry.insert((priority, callback));
+                break index;
+            }
+        }
+    }
+
+    /// State needed for dispatch that is observed together with handlers
+    fn collected(&self) -> Collected {
+        Collected {
+            middleware: self.middleware.clone(),
+            #[cfg(feature = "debug-checks")]
+            version: self.version,
+        }
+    }
+
+    /// Record that set of handlers has changed, does nothing without `debug-checks` feature
+    #[inline(always)]
+    fn mark_changed(&mut self) {
+        #[cfg(feature = "debug-checks")]
+        {
+            self.version = self.version.wrapping_add(1);
+        }
+    }
+}
+
+impl<F: Send + Sync + Clone + 'static> Drop for Inner<F> {
+    fn drop(&mut self) {
+        drop_in_reverse_order(map::drain(&mut self.handlers).collect(), None);
+    }
+}
+
+/// Shuffle handlers with xorshift PRNG seeded with `seed`, same seed results in the same order
+fn shuffle_handlers<T>(handlers: &mut [T], seed: u64) {
+    // Xorshift gets stuck at zero, so seed is mixed with a constant first
+    let mut state = seed ^ 0x9E37_79B9_7F4A_7C15;
+    if state == 0 {
+        state = 0x9E37_79B9_7F4A_7C15;
+    }
+    // Fisher-Yates shuffle
+    for i in (1..handlers.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let j = (state % (i as u64 + 1)) as usize;
+        handlers.swap(i, j);
+    }
+}
+
+/// Drop handlers one by one starting with the most recently added one, reporting each removal to
+/// `hook`
+fn drop_in_reverse_order<T>(mut handlers: SmallVec<[(usize, T); 2]>, hook: Option<ChangeHook>) {
+    handlers.sort_unstable_by_key(|(index, _)| Reverse(*index));
+    for (index, handler) in handlers {
+        drop(handler);
+        notify(hook.clone(), ChangeKind::Removed, index);
+    }
+}
+
+type ChangeHook = Arc<dyn Fn(ChangeKind, usize) + Send + Sync + 'static>;
+type Middleware = Arc<dyn Fn(usize, &mut dyn FnMut()) + Send + Sync + 'static>;
+/// Handlers with their priorities and indices
+type Handlers<F> = SmallVec<[(i32, usize, F); 2]>;
+
+/// State observed together with handlers when they are collected for a call
+pub(crate) struct Collected {
+    middleware: Option<Middleware>,
+    #[cfg(feature = "debug-checks")]
+    version: u64,
+}
+
+impl Collected {
+    /// Run `call` for handler with specified index through middleware if there is one, returns
+    /// result of the last run or `None` if middleware didn't run it
+    pub(crate) fn invoke<R, C>(&self, index: usize, mut call: C) -> Option<R>
+    where
+        C: FnMut() -> R,
+    {
+        match &self.middleware {
+            Some(middleware) => {
+                let mut result = None;
+                middleware(index, &mut || result = Some(call()));
+                result
+            }
+            None => Some(call()),
+        }
+    }
+}
+
+/// Indices of handlers in a bag, returned by [`Bag::index_snapshot()`]
+///
+/// Only contains metadata, handlers themselves are not included.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexSnapshot {
+    /// Indices of handlers currently in the bag in increasing order
+    pub indices: Vec<usize>,
+    /// Index that will be tried first for the next added handler
+    pub next_index: usize,
+}
+
+/// How handlers are accessed during [`Bag::call_with_mode()`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DispatchMode {
+    /// Handlers are cloned before the first one is called and no lock is held while they are
+    /// running, same as [`Bag::call()`]
+    #[default]
+    Snapshot,
+    /// Handlers are called by reference while holding the lock, same as [`Bag::call_locked()`]
+    ///
+    /// Avoids cloning handlers, but handlers must not access the bag (add or remove handlers, call
+    /// it, drop handler IDs, etc.) since doing so may deadlock.
+    Live,
+}
+
+/// Outcome of [`Bag::call_reporting()`] and `Bag::call_with_deadline()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DispatchOutcome {
+    /// Number of handlers called
+    pub handlers_invoked: usize,
+    /// Number of handlers that were not called because deadline was reached
+    pub handlers_skipped: usize,
+}
+
+impl DispatchOutcome {
+    /// Returns `true` if at least one handler was called
+    pub fn fired(&self) -> bool {
+        self.handlers_invoked > 0
+    }
+}
+
+/// Kind of change reported to the hook installed with [`Bag::on_change()`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Handler was added to the bag
+    Added,
+    /// Handler was removed from the bag
+    Removed,
+}
+
+/// Call change hook if there is one, must be called without holding the lock
+fn notify(hook: Option<ChangeHook>, kind: ChangeKind, index: usize) {
+    if let Some(hook) = hook {
+        hook(kind, index);
+    }
+}
+
+/// Sort handlers with their priorities and indices in the order they should be called
+fn sort_handlers<T>(handlers: &mut [(i32, usize, T)]) {
+    // Indices are assigned in increasing order, so sorting by them restores insertion order within
+    // the same priority
+    handlers.sort_unstable_by_key(|(priority, index, _)| (Reverse(*priority), *index));
 }
 
 /// Data structure that holds `Fn()` event handlers
+///
+/// Handlers are stored behind a read-write lock, so concurrent calls don't block each other, only
+/// adding and removing handlers does.
+///
+/// Handlers are stored as `F` without any additional boxing, so `F` is typically `Arc<T>` where `T`
+/// is a closure or `dyn Fn() + Send + Sync`, which makes a single allocation per handler and a
+/// cheap clone while collecting handlers in [`Bag::call()`].
+///
+/// `Sync` is required because the same handler can be called from multiple threads at once:
+/// clones of the bag are called concurrently and [`Bag::call_locked()`] gives out references to
+/// handlers while others can do the same. Use [`BagOnce`](crate::BagOnce) for handlers that are
+/// only `Send` and need to be called once, or [`LocalBag`](crate::LocalBag) for handlers that
+/// are neither `Send` nor `Sync` when the bag never leaves its thread.
+///
+/// Bags that are only used from a single thread, but need to be `Send` to be stored in a shared
+/// structure, never contend on the lock, and uncontended locking is a single atomic operation.
+/// There is no lock-free mode for such bags, since checking the owning thread on every access
+/// would cost about as much as the lock itself while making the bag `!Sync` in practice.
+///
+/// Once the bag is cleared or the last instance of the bag is dropped, handlers are dropped in
+/// reverse order of their addition (regardless of priority), such that resources captured by
+/// handlers added later can rely on resources captured by handlers added earlier. Order of
+/// addition is tracked by handler indices, so handlers added after indices wrapped around (see
+/// [`HandlerId::index()`]) are dropped last.
+///
+/// Arguments of `call_simple()` must be `Sized`, handlers that take unsized arguments like
+/// `&dyn Trait` can be called with [`Bag::call()`] instead, for example
+/// `bag.call(|handler| handler(&value))` for `Bag<Arc<dyn Fn(&dyn Trait) + Send + Sync>>`.
+///
+/// Context shared by all handlers doesn't need to be captured by each of them, it can be passed
+/// at call time as one of the arguments instead, for example with
+/// `Bag<Arc<dyn Fn(&C, &A1) + Send + Sync>, C, A1>` and `bag.call_simple(&context, &a1)`.
 pub struct Bag<
     F: Send + Sync + Clone + 'static,
     A1: ?Sized = private::Private,
@@ -27,16 +278,1326 @@ pub struct Bag<
     A3: ?Sized = private::Private,
     A4: ?Sized = private::Private,
     A5: ?Sized = private::Private,
+    A6: ?Sized = private::Private,
+> {
+    id: BagId,
+    inner: Arc<RwLock<Inner<F>>>,
+    /// Indices of handlers whose handler IDs were dropped, but that are not removed yet, only used
+    /// by bags created with [`Bag::with_deferred_removal()`]; stored outside of the lock so that
+    /// checking it doesn't need one
+    pending_removal: Option<Arc<Mutex<Vec<usize>>>>,
+    a1: PhantomData<A1>,
+    a2: PhantomData<A2>,
+    a3: PhantomData<A3>,
+    a4: PhantomData<A4>,
+    a5: PhantomData<A5>,
+    a6: PhantomData<A6>,
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> fmt::Debug for Bag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Send + Sync + Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug_struct = f.debug_struct("Bag");
+        // Bag might be locked by the caller, `try_lock` avoids deadlock in such case
+        let indices = self.inner.try_read().map(|inner| {
+            let mut indices = inner.handlers.keys().copied().collect::<Vec<_>>();
+            indices.sort_unstable();
+            (inner.name, indices)
+        });
+        match indices {
+            Some((name, indices)) => {
+                if let Some(name) = name {
+                    debug_struct.field("name", &name);
+                }
+                debug_struct
+                    .field("handler_count", &indices.len())
+                    .field("indices", &indices)
+            }
+            None => debug_struct.field("handlers", &format_args!("<locked>")),
+        };
+        debug_struct.finish()
+    }
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> Clone for Bag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Send + Sync + Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            inner: Arc::clone(&self.inner),
+            pending_removal: self.pending_removal.clone(),
+            a1: PhantomData,
+            a2: PhantomData,
+            a3: PhantomData,
+            a4: PhantomData,
+            a5: PhantomData,
+            a6: PhantomData,
+        }
+    }
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> Default for Bag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Send + Sync + Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    fn default() -> Self {
+        Self::with_handlers(Map::default(), None)
+    }
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> Bag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Send + Sync + Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    /// Create new bag with space for at least `capacity` handlers preallocated
+    #[cfg(feature = "std")]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_handlers(
+            Map::with_capacity_and_hasher(capacity, Default::default()),
+            None,
+        )
+    }
+
+    /// Create new bag that can hold at most `max` handlers added with [`Bag::try_add()`]
+    ///
+    /// [`Bag::add()`] and other methods of adding handlers ignore the limit.
+    pub fn with_limit(max: usize) -> Self {
+        Self::with_handlers(Map::default(), Some(max))
+    }
+
+    /// Create new bag with a name that is shown in `Debug` output and `tracing` spans
+    pub fn new_named(name: &'static str) -> Self {
+        let bag = Self::default();
+        bag.inner.write().name.replace(name);
+
+        bag
+    }
+
+    /// Create new bag where dropping handler ID never blocks on the bag's lock
+    ///
+    /// Handler is queued for removal instead and removed before handlers are called, taken out of
+    /// the bag, retained or forked next time, or when the next handler is added, until then it is
+    /// still counted by [`Bag::len()`]. [`Bag::call_nonblocking()`] and `Bag::call_timeout()` skip
+    /// queued handlers without removing them, since that may require waiting for the lock.
+    ///
+    /// This makes it safe to drop handler IDs (including with [`HandlerId::remove_now()`]) while
+    /// the bag is locked, for example from within [`Bag::call_locked()`] or a predicate of
+    /// [`Bag::retain()`]. Handlers removed this way are reported to the hook installed with
+    /// [`Bag::on_change()`] once they are actually removed.
+    pub fn with_deferred_removal() -> Self {
+        Self {
+            pending_removal: Some(Arc::new(Mutex::new(Vec::new()))),
+            ..Self::default()
+        }
+    }
+
+    /// Name of the bag provided to [`Bag::new_named()`], `None` for bags created otherwise
+    pub fn name(&self) -> Option<&'static str> {
+        self.inner.read().name
+    }
+
+    /// Add new event handler to a bag with default priority `0`
+    pub fn add(&self, callback: F) -> HandlerId {
+        self.add_with_priority(callback, 0)
+    }
+
+    /// Add multiple event handlers to a bag with default priority `0` at once, returns handler IDs
+    /// in the same order as handlers
+    ///
+    /// Bag is locked only once for all handlers.
+    pub fn add_all<I>(&self, callbacks: I) -> Vec<HandlerId>
+    where
+        I: IntoIterator<Item = F>,
+    {
+        // Collect handlers first in order to avoid running iterator while holding lock
+        let callbacks = callbacks.into_iter().collect::<Vec<F>>();
+        self.process_pending_removal();
+        let (indices, hook) = {
+            let mut inner = self.inner.write();
+            let indices = callbacks
+                .into_iter()
+                .map(|callback| inner.insert(callback, 0))
+                .collect::<Vec<usize>>();
+            (indices, inner.on_change.clone())
+        };
+
+        indices
+            .into_iter()
+            .map(|index| {
+                notify(hook.clone(), ChangeKind::Added, index);
+                HandlerId::new(self.id, index, self.remover(index))
+            })
+            .collect()
+    }
+
+    /// Add new event handler to a bag with default priority `0` and attach `data` to returned
+    /// handler ID
+    pub fn add_with_data<D>(&self, callback: F, data: D) -> TaggedHandlerId<D> {
+        TaggedHandlerId::new(self.add(callback), data)
+    }
+
+    /// Add new event handler to a bag with default priority `0` permanently
+    ///
+    /// Same as `bag.add(callback).detach()`, handler stays in the bag until removed with
+    /// [`Bag::clear()`] or similar methods.
+    pub fn add_detached(&self, callback: F) {
+        self.insert(callback, 0);
+    }
+
+    /// Add new event handler to a bag with specified priority
+    ///
+    /// Handlers with higher priority are called before handlers with lower priority, handlers with
+    /// the same priority are called in the order they were added to the bag.
+    pub fn add_with_priority(&self, callback: F, priority: i32) -> HandlerId {
+        let index = self.insert(callback, priority);
+
+        HandlerId::new(self.id, index, self.remover(index))
+    }
+
+    /// Add new event handler to a bag with default priority `0` unless bag already holds maximum
+    /// number of handlers set with [`Bag::with_limit()`]
+    pub fn try_add(&self, callback: F) -> Result<HandlerId, CapacityError> {
+        // Handlers queued for removal must not count against the limit
+        self.process_pending_removal();
+        let (index, hook) = {
+            let mut inner = self.inner.write();
+
+            if let Some(max) = inner.limit {
+                let current = inner.handlers.len();
+                if current >= max {
+                    return Err(CapacityError { current, max });
+                }
+            }
+
+            (inner.insert(callback, 0), inner.on_change.clone())
+        };
+        notify(hook, ChangeKind::Added, index);
+
+        Ok(HandlerId::new(self.id, index, self.remover(index)))
+    }
+
+    /// Add new event handler to a bag with default priority `0` and return its key alongside
+    /// handler ID
+    ///
+    /// Key can be used to remove handler with [`Bag::remove_key()`].
+    pub fn add_keyed(&self, callback: F) -> (HandlerId, HandlerKey) {
+        let index = self.insert(callback, 0);
+
+        (
+            HandlerId::new(self.id, index, self.remover(index)),
+            HandlerKey::new(self.id, index),
+        )
+    }
+
+    /// Install hook that is called with index of the handler every time handler is added to or
+    /// removed from the bag, replaces previously installed hook
+    ///
+    /// Hook is called without holding the lock. Every removal is reported, including removals in
+    /// bulk with [`Bag::clear()`], [`Bag::retain()`], [`Bag::drain()`] and [`Bag::call_once()`]
+    /// (before handlers are called), but not dropping of handlers together with the bag itself.
+    pub fn on_change<H>(&self, hook: H)
+    where
+        H: Fn(ChangeKind, usize) + Send + Sync + 'static,
+    {
+        self.inner.write().on_change.replace(Arc::new(hook));
+    }
+
+    /// Install hook that is called when set of handlers has changed while [`Bag::call()`] was
+    /// dispatching, replaces previously installed hook
+    ///
+    /// Handlers added or removed during the call (by handlers themselves or concurrently from
+    /// other threads) are not reflected in the set of handlers being called, which is safe, but
+    /// might hide subtle subscription races, this hook helps to surface them in tests. Hook is
+    /// called after the last handler without holding the lock.
+    #[cfg(feature = "debug-checks")]
+    pub fn on_change_during_call<H>(&self, hook: H)
+    where
+        H: Fn() + Send + Sync + 'static,
+    {
+        self.inner
+            .write()
+            .on_change_during_call
+            .replace(Arc::new(hook));
+    }
+
+    /// Install middleware that runs around each handler called by any of the `call*` methods of the
+    /// bag and by [`Callable::call()`](crate::Callable::call), replaces previously installed
+    /// middleware
+    ///
+    /// Middleware is called with index of the handler and a function that calls the handler, it
+    /// is up to middleware to call it (usually exactly once), which allows instrumenting dispatch
+    /// uniformly, for example measuring time spent in each handler. Middleware is called without
+    /// holding the lock, except for [`Bag::call_locked()`].
+    ///
+    /// When middleware doesn't call the handler, [`Bag::call_first()`] returns `None`,
+    /// [`Bag::call_until()`] and [`Bag::try_call_stop()`] continue with the next handler and
+    /// [`Bag::call_retaining()`] keeps the handler. [`Callable::call()`](crate::Callable::call)
+    /// gives handler to applicator by value, so only the first call from middleware has effect.
+    pub fn set_middleware<M>(&self, middleware: M)
+    where
+        M: Fn(usize, &mut dyn FnMut()) + Send + Sync + 'static,
+    {
+        self.inner.write().middleware.replace(Arc::new(middleware));
+    }
+
+    /// Number of handlers currently in the bag
+    pub fn len(&self) -> usize {
+        self.inner.read().handlers.len()
+    }
+
+    /// Returns `true` if there are no handlers in the bag
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().handlers.is_empty()
+    }
+
+    /// Number of instances of this bag, including this one
+    ///
+    /// Handler IDs and [`WeakBag`]s don't keep the bag alive and are not counted, but clones of the
+    /// bag captured by handlers are.
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+
+    /// Returns `true` if this is the only instance of the bag, see [`Bag::strong_count()`]
+    pub fn is_unique(&self) -> bool {
+        self.strong_count() == 1
+    }
+
+    /// Number of handlers the bag can hold without reallocating
+    #[cfg(feature = "std")]
+    pub fn capacity(&self) -> usize {
+        self.inner.read().handlers.capacity()
+    }
+
+    /// Reserve space for at least `additional` more handlers to be added to the bag
+    #[cfg(feature = "std")]
+    pub fn reserve(&self, additional: usize) {
+        self.inner.write().handlers.reserve(additional);
+    }
+
+    /// Shrink capacity of the bag as much as possible to reclaim memory after handlers were removed
+    #[cfg(feature = "std")]
+    pub fn shrink_to_fit(&self) {
+        self.inner.write().handlers.shrink_to_fit();
+    }
+
+    /// Returns `true` if handler ID was returned by this bag (or its clones)
+    ///
+    /// Handler might have been removed from the bag already, merged handler IDs don't belong to
+    /// any bag.
+    pub fn owns(&self, handler_id: &HandlerId) -> bool {
+        matches!(handler_id.key(), Some((bag_id, _index)) if bag_id == self.id)
+    }
+
+    /// Remove handler from the bag without waiting for handler ID to be dropped.
+    ///
+    /// Returns `false` if handler ID belongs to a different bag, was merged from multiple handler
+    /// IDs or handler was already removed, handler ID is detached afterwards and will do nothing
+    /// when dropped.
+    pub fn remove(&self, handler_id: &HandlerId) -> bool {
+        let index = match handler_id.key() {
+            Some((bag_id, index)) if bag_id == self.id => index,
+            _ => {
+                return false;
+            }
+        };
+
+        let removed = Self::remove_index(&self.inner, index);
+        handler_id.disarm();
+
+        removed
+    }
+
+    /// Remove multiple handlers from the bag at once, locking the bag only once
+    ///
+    /// Handler IDs that belong to a different bag or were merged from multiple handler IDs are
+    /// ignored, the rest are detached afterwards and will do nothing when dropped. Returns number
+    /// of handlers removed.
+    pub fn remove_many(&self, handler_ids: &[HandlerId]) -> usize {
+        let (removed, hook) = {
+            let mut inner = self.inner.write();
+            let removed = handler_ids
+                .iter()
+                .filter_map(|handler_id| match handler_id.key() {
+                    Some((bag_id, index)) if bag_id == self.id => {
+                        handler_id.disarm();
+                        Some((index, inner.handlers.remove(&index)?))
+                    }
+                    _ => None,
+                })
+                .collect::<SmallVec<[(usize, (i32, F)); 2]>>();
+            if !removed.is_empty() {
+                inner.mark_changed();
+            }
+            (removed, inner.on_change.clone())
+        };
+        let count = removed.len();
+        // Handlers are dropped after lock is released
+        for (index, _handler) in removed {
+            notify(hook.clone(), ChangeKind::Removed, index);
+        }
+
+        count
+    }
+
+    /// Replace handler with a new one atomically, such that concurrent calls observe either old or
+    /// new handler, but never neither of them
+    ///
+    /// New handler gets priority of the old one. If handler ID belongs to a different bag, was
+    /// merged from multiple handler IDs or handler was already removed, new handler is added with
+    /// default priority `0`. Old handler ID that belongs to this bag is detached afterwards and
+    /// will do nothing when dropped, other handler IDs are left untouched.
+    pub fn replace(&self, handler_id: &HandlerId, callback: F) -> HandlerId {
+        let old_index = match handler_id.key() {
+            Some((bag_id, index)) if bag_id == self.id => Some(index),
+            _ => None,
+        };
+
+        self.process_pending_removal();
+        let (old_handler, index, hook) = {
+            let mut inner = self.inner.write();
+            let old_handler = old_index.and_then(|index| inner.handlers.remove(&index));
+            if old_handler.is_some() {
+                inner.mark_changed();
+            }
+            let priority = old_handler
+                .as_ref()
+                .map_or(0, |(priority, _handler)| *priority);
+            let index = inner.insert(callback, priority);
+            (old_handler, index, inner.on_change.clone())
+        };
+        if old_index.is_some() {
+            handler_id.disarm();
+        }
+        // Old handler is dropped after lock is released
+        if let (Some(old_index), Some(_old_handler)) = (old_index, old_handler) {
+            notify(hook.clone(), ChangeKind::Removed, old_index);
+        }
+        notify(hook, ChangeKind::Added, index);
+
+        HandlerId::new(self.id, index, self.remover(index))
+    }
+
+    /// Create new handler ID for a handler that is still in the bag, for example after
+    /// [`HandlerId::leak()`]
+    ///
+    /// Returns `None` if key belongs to a different bag or handler was already removed. Handler
+    /// will be removed once any of handler IDs created for it is dropped.
+    pub fn reclaim(&self, key: HandlerKey) -> Option<HandlerId> {
+        if key.bag_id() != self.id || !self.inner.read().handlers.contains_key(&key.index()) {
+            return None;
+        }
+
+        Some(HandlerId::new(
+            self.id,
+            key.index(),
+            self.remover(key.index()),
+        ))
+    }
+
+    /// Remove handler from the bag by its key.
+    ///
+    /// Returns `false` if key belongs to a different bag or handler was already removed, handler ID
+    /// of removed handler will do nothing when dropped.
+    pub fn remove_key(&self, key: HandlerKey) -> bool {
+        if key.bag_id() != self.id {
+            return false;
+        }
+
+        Self::remove_index(&self.inner, key.index())
+    }
+
+    /// Remove all handlers from the bag
+    ///
+    /// Handlers are dropped in reverse order of their addition, except for handlers added after
+    /// indices wrapped around (see [`HandlerId::index()`]), which are dropped last. Handler IDs of
+    /// removed handlers will do nothing when dropped.
+    pub fn clear(&self) {
+        let (handlers, hook) = {
+            let mut inner = self.inner.write();
+            if !inner.handlers.is_empty() {
+                inner.mark_changed();
+            }
+            (
+                map::drain(&mut inner.handlers).collect(),
+                inner.on_change.clone(),
+            )
+        };
+        // Handlers are dropped after lock is released
+        drop_in_reverse_order(handlers, hook);
+    }
+
+    /// Retain only handlers for which predicate returns `true`, the rest are removed from the bag
+    ///
+    /// Predicate is called while bag is locked, so it must not access the bag itself. Handler IDs
+    /// of removed handlers will do nothing when dropped.
+    pub fn retain<P>(&self, mut predicate: P)
+    where
+        P: FnMut(&F) -> bool,
+    {
+        self.process_pending_removal();
+        let (removed, hook) = {
+            let mut inner = self.inner.write();
+            let mut indices = inner
+                .handlers
+                .iter()
+                .filter(|(_index, (_priority, handler))| !predicate(handler))
+                .map(|(index, _handler)| *index)
+                .collect::<SmallVec<[usize; 2]>>();
+            indices.sort_unstable();
+            let removed = indices
+                .into_iter()
+                .filter_map(|index| Some((index, inner.handlers.remove(&index)?)))
+                .collect::<SmallVec<[(usize, (i32, F)); 2]>>();
+            if !removed.is_empty() {
+                inner.mark_changed();
+            }
+            (removed, inner.on_change.clone())
+        };
+        // Handlers are dropped after lock is released
+        for (index, handler) in removed {
+            drop(handler);
+            notify(hook.clone(), ChangeKind::Removed, index);
+        }
+    }
+
+    /// Call applicator with each handler and keep handlers in the bag
+    ///
+    /// Handlers are called in order of their priority and then in the same order in which they
+    /// were added to the bag, returns number of handlers called. The only exception are handlers
+    /// added after indices wrapped around (see [`HandlerId::index()`]), which are called before
+    /// older handlers with the same priority.
+    ///
+    /// Set of handlers is collected before the first handler is called and no lock is held while
+    /// handlers are running, so handlers can use the bag itself without deadlocking:
+    /// * handlers added from within handlers will not be called until the next call
+    /// * handlers removed (or cleared) from within handlers or concurrently from other threads,
+    ///   including by dropping their handler IDs, will still be called during this call
+    /// * nested call from within handler will call all handlers that are in the bag at that moment
+    ///
+    /// Collecting more than 2 handlers allocates, use [`Bag::call_with_buffer()`] to reuse memory
+    /// across calls instead.
+    ///
+    /// With `tracing` feature enabled dispatch is wrapped in `bag.call` span and an event with
+    /// handler index is emitted before each handler is called.
+    pub fn call<A>(&self, applicator: A) -> usize
+    where
+        A: FnMut(&F),
+    {
+        self.call_with_buffer(&mut DispatchBuffer::new(), applicator)
+    }
+
+    /// Call applicator with each handler and keep handlers in the bag, accessing handlers according
+    /// to `mode`, returns number of handlers called
+    ///
+    /// Handlers are called in the same order as in [`Bag::call()`], see [`DispatchMode`] for
+    /// constraints of each mode.
+    pub fn call_with_mode<A>(&self, mode: DispatchMode, applicator: A) -> usize
+    where
+        A: FnMut(&F),
+    {
+        match mode {
+            DispatchMode::Snapshot => self.call(applicator),
+            DispatchMode::Live => self.call_locked(applicator),
+        }
+    }
+
+    /// Same as [`Bag::call()`], but returns [`DispatchOutcome`] that makes it explicit at call site
+    /// whether anyone was listening
+    pub fn call_reporting<A>(&self, applicator: A) -> DispatchOutcome
+    where
+        A: FnMut(&F),
+    {
+        DispatchOutcome {
+            handlers_invoked: self.call(applicator),
+            handlers_skipped: 0,
+        }
+    }
+
+    /// Call applicator with each handler and keep handlers in the bag until `budget` is exhausted,
+    /// remaining handlers are skipped afterwards
+    ///
+    /// Handlers are called in the same order as in [`Bag::call()`]. Handlers that are already
+    /// running can't be interrupted, so the call may take longer than `budget`, but no handler is
+    /// started once time is up.
+    #[cfg(feature = "std")]
+    pub fn call_with_deadline<A>(&self, mut applicator: A, budget: Duration) -> DispatchOutcome
+    where
+        A: FnMut(&F),
+    {
+        let start = Instant::now();
+        let (handlers, collected) = self.handlers();
+        let mut handlers_invoked = 0;
+        for (_, index, handler) in handlers.iter() {
+            if start.elapsed() >= budget {
+                break;
+            }
+            collected.invoke(*index, || applicator(handler));
+            handlers_invoked += 1;
+        }
+
+        DispatchOutcome {
+            handlers_invoked,
+            handlers_skipped: handlers.len() - handlers_invoked,
+        }
+    }
+
+    /// Call applicator with each handler and keep handlers in the bag, reusing memory of provided
+    /// buffer for collecting handlers
+    ///
+    /// Same as [`Bag::call()`], but doesn't allocate once buffer is large enough to hold all
+    /// handlers. Contents of the buffer are cleared on entry and after handlers are called, such
+    /// that it doesn't keep handlers alive between calls, only its memory is reused.
+    pub fn call_with_buffer<A>(&self, buffer: &mut DispatchBuffer<F>, mut applicator: A) -> usize
+    where
+        A: FnMut(&F),
+    {
+        let collected = self.collect_handlers(&mut buffer.handlers);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "bag.call",
+            name = self.name(),
+            handlers = buffer.handlers.len()
+        )
+        .entered();
+        for (_, index, handler) in buffer.handlers.iter() {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(index, "calling handler");
+            collected.invoke(*index, || applicator(handler));
+        }
+        #[cfg(feature = "debug-checks")]
+        self.check_version(collected.version);
+        let count = buffer.handlers.len();
+        buffer.handlers.clear();
+
+        count
+    }
+
+    /// Call applicator with each handler while holding the lock and keep handlers in the bag
+    ///
+    /// Same as [`Bag::call()`], but handlers are not cloned, which is cheaper. Bag is locked for
+    /// reading for the whole duration of the call, so other threads can call handlers concurrently,
+    /// but handlers must not access this bag (add or remove handlers, call it, drop handler IDs,
+    /// etc.) since doing so may deadlock.
+    pub fn call_locked<A>(&self, mut applicator: A) -> usize
+    where
+        A: FnMut(&F),
+    {
+        self.process_pending_removal();
+        let inner = self.inner.read();
+        let collected = inner.collected();
+        let mut handlers = inner
+            .handlers
+            .iter()
+            .map(|(index, (priority, handler))| (*priority, *index, handler))
+            .collect::<SmallVec<[(i32, usize, &F); 2]>>();
+        sort_handlers(&mut handlers);
+        for (_, index, handler) in handlers.iter() {
+            collected.invoke(*index, || applicator(handler));
+        }
+
+        handlers.len()
+    }
+
+    /// Call applicator with each handler and keep handlers in the bag unless bag is locked by
+    /// another thread
+    ///
+    /// Same as [`Bag::call()`], but returns [`WouldBlock`] error instead of waiting if bag is
+    /// locked for writing (handlers are being added or removed) at the moment, in which case no
+    /// handlers are called.
+    pub fn call_nonblocking<A>(&self, mut applicator: A) -> Result<usize, WouldBlock>
+    where
+        A: FnMut(&F),
+    {
+        let mut handlers = SmallVec::new();
+        let collected = {
+            let inner = self.inner.try_read().ok_or(WouldBlock)?;
+            inner.clone_handlers_into(&mut handlers);
+            inner.collected()
+        };
+        self.skip_pending_removal(&mut handlers);
+        sort_handlers(&mut handlers);
+        for (_, index, handler) in handlers.iter() {
+            collected.invoke(*index, || applicator(handler));
+        }
+
+        Ok(handlers.len())
+    }
+
+    /// Call applicator with each handler and keep handlers in the bag unless bag stays locked by
+    /// another thread for longer than `timeout`
+    ///
+    /// Same as [`Bag::call()`], but returns [`Timeout`] error if lock wasn't acquired in time, in
+    /// which case no handlers are called.
+    #[cfg(feature = "parking_lot")]
+    pub fn call_timeout<A>(&self, mut applicator: A, timeout: Duration) -> Result<usize, Timeout>
+    where
+        A: FnMut(&F),
+    {
+        let mut handlers = SmallVec::new();
+        let collected = {
+            let inner = self.inner.try_read_for(timeout).ok_or(Timeout)?;
+            inner.clone_handlers_into(&mut handlers);
+            inner.collected()
+        };
+        self.skip_pending_removal(&mut handlers);
+        sort_handlers(&mut handlers);
+        for (_, index, handler) in handlers.iter() {
+            collected.invoke(*index, || applicator(handler));
+        }
+
+        Ok(handlers.len())
+    }
+
+    /// Collect handlers and call applicator with each of them inside a task given to `spawn`,
+    /// keeping handlers in the bag, returns number of handlers that will be called
+    ///
+    /// Handlers are collected right away and called in the same order as in [`Bag::call()`] once
+    /// `spawn` runs the task, for example on a background thread or an executor, such that the
+    /// caller doesn't pay for handlers inline. Handlers added or removed in between don't affect
+    /// the set of handlers called.
+    pub fn call_on<S, A>(&self, spawn: S, mut applicator: A) -> usize
+    where
+        S: FnOnce(Box<dyn FnOnce() + Send + 'static>),
+        A: FnMut(&F) + Send + 'static,
+    {
+        let (handlers, collected) = self.handlers();
+        let count = handlers.len();
+        spawn(Box::new(move || {
+            for (_, index, handler) in handlers.iter() {
+                collected.invoke(*index, || applicator(handler));
+            }
+        }));
+
+        count
+    }
+
+    /// Call applicator with each handler and remove handlers from the bag
+    ///
+    /// Handlers are called in the same order as in [`Bag::call()`], returns number of handlers
+    /// called. Handler IDs of removed handlers will do nothing when dropped.
+    pub fn call_once<A>(&self, mut applicator: A) -> usize
+    where
+        A: FnMut(&F),
+    {
+        // We take handlers out first in order to avoid holding lock while calling handlers
+        let (handlers, collected) = self.take_handlers();
+        for (_, index, handler) in handlers.iter() {
+            collected.invoke(*index, || applicator(handler));
+        }
+
+        handlers.len()
+    }
+
+    /// Remove all handlers from the bag and return them in the same order as in [`Bag::call()`]
+    ///
+    /// Unlike [`Bag::clear()`] handlers are given back to the caller, for example to add them to a
+    /// different bag. Handler IDs of removed handlers will do nothing when dropped.
+    pub fn drain(&self) -> impl ExactSizeIterator<Item = F> {
+        self.take_handlers()
+            .0
+            .into_iter()
+            .map(|(_priority, _index, handler)| handler)
+    }
+
+    /// Call applicator with position of each handler in the call (starting from `0`) and the
+    /// handler itself, keep handlers in the bag
+    ///
+    /// Handlers are called in the same order as in [`Bag::call()`], returns number of handlers
+    /// called.
+    pub fn call_enumerated<A>(&self, mut applicator: A) -> usize
+    where
+        A: FnMut(usize, &F),
+    {
+        let mut position = 0;
+        self.call(|handler| {
+            applicator(position, handler);
+            position += 1;
+        })
+    }
+
+    /// Call applicator with the first handler only and keep handlers in the bag, returns result of
+    /// the applicator or `None` if there are no handlers
+    ///
+    /// First handler is the one that would be called first by [`Bag::call()`], useful for
+    /// request/response patterns where exactly one handler is expected.
+    pub fn call_first<R, A>(&self, applicator: A) -> Option<R>
+    where
+        A: FnOnce(&F) -> R,
+    {
+        self.process_pending_removal();
+        // Handler is cloned in order to avoid holding lock while calling it
+        let (index, handler, collected) = {
+            let inner = self.inner.read();
+            let (index, handler) = inner
+                .handlers
+                .iter()
+                .min_by_key(|(index, (priority, _handler))| (Reverse(*priority), **index))
+                .map(|(index, (_priority, handler))| (*index, handler.clone()))?;
+            (index, handler, inner.collected())
+        };
+        let mut applicator = Some(applicator);
+
+        collected
+            .invoke(index, || {
+                applicator.take().map(|applicator| applicator(&handler))
+            })
+            .flatten()
+    }
+
+    /// Call applicator with each handler and remove handlers for which applicator returned
+    /// `false` once all handlers were called
+    ///
+    /// Handlers are called in the same order as in [`Bag::call()`], returns number of handlers
+    /// called. This allows handlers to fire once at different times or to remove themselves when
+    /// they are no longer needed, for example when upgrading a captured `Weak` reference fails.
+    /// Handler IDs of removed handlers will do nothing when dropped.
+    pub fn call_retaining<A>(&self, mut applicator: A) -> usize
+    where
+        A: FnMut(&F) -> bool,
+    {
+        let (handlers, collected) = self.handlers();
+        let mut remove = SmallVec::<[usize; 2]>::new();
+        for (_, index, handler) in handlers.iter() {
+            if collected.invoke(*index, || applicator(handler)) == Some(false) {
+                remove.push(*index);
+            }
+        }
+        for index in remove {
+            Self::remove_index(&self.inner, index);
+        }
+
+        handlers.len()
+    }
+
+    /// Call applicator with each handler and keep handlers in the bag, starting from a different
+    /// handler on each call
+    ///
+    /// Handlers are called in the same order as in [`Bag::call()`], but dispatch starts at the
+    /// position of an internal cursor (modulo number of handlers) and wraps around, after which the
+    /// cursor is advanced. Successive calls start with successive handlers, which spreads cost of
+    /// being called first across handlers. Returns number of handlers called.
+    pub fn call_rotating<A>(&self, mut applicator: A) -> usize
+    where
+        A: FnMut(&F),
+    {
+        let mut handlers = SmallVec::<[(i32, usize, F); 2]>::new();
+        self.process_pending_removal();
+        let (cursor, collected) = {
+            let inner = self.inner.read();
+            inner.clone_handlers_into(&mut handlers);
+            (
+                inner.cursor.fetch_add(1, Ordering::Relaxed),
+                inner.collected(),
+            )
+        };
+        if handlers.is_empty() {
+            return 0;
+        }
+        sort_handlers(&mut handlers);
+        let offset = cursor % handlers.len();
+        handlers.rotate_left(offset);
+        for (_, index, handler) in handlers.iter() {
+            collected.invoke(*index, || applicator(handler));
+        }
+
+        handlers.len()
+    }
+
+    /// Call applicator with each handler and keep handlers in the bag, calling handlers in pseudo
+    /// random order determined by `seed`
+    ///
+    /// Same seed results in the same order for the same set of handlers. This is a testing aid
+    /// that helps to discover accidental reliance on the order in which handlers are called.
+    /// Returns number of handlers called.
+    pub fn call_shuffled<A>(&self, seed: u64, mut applicator: A) -> usize
+    where
+        A: FnMut(&F),
+    {
+        let (mut handlers, collected) = self.handlers();
+        shuffle_handlers(&mut handlers, seed);
+        for (_, index, handler) in handlers.iter() {
+            collected.invoke(*index, || applicator(handler));
+        }
+
+        handlers.len()
+    }
+
+    /// Call applicator with each handler, keep handlers in the bag and collect applicator results
+    /// in the order handlers were called
+    pub fn call_collect<R, A>(&self, mut applicator: A) -> Vec<R>
+    where
+        A: FnMut(&F) -> R,
+    {
+        let mut results = Vec::new();
+        self.call(|handler| {
+            results.push(applicator(handler));
+        });
+
+        results
+    }
+
+    /// Call applicator with each handler until it returns [`ControlFlow::Break`] and keep
+    /// handlers in the bag
+    ///
+    /// Returns value from [`ControlFlow::Break`] or `None` if applicator returned
+    /// [`ControlFlow::Continue`] for all handlers. Handlers are called in the same order as in
+    /// [`Bag::call()`], remaining handlers are not called after the first [`ControlFlow::Break`].
+    pub fn call_until<B, A>(&self, mut applicator: A) -> Option<B>
+    where
+        A: FnMut(&F) -> ControlFlow<B>,
+    {
+        let (handlers, collected) = self.handlers();
+        for (_, index, handler) in handlers.iter() {
+            if let Some(ControlFlow::Break(value)) =
+                collected.invoke(*index, || applicator(handler))
+            {
+                return Some(value);
+            }
+        }
+
+        None
+    }
+
+    /// Call applicator with each fallible handler and keep handlers in the bag
+    ///
+    /// All handlers are called regardless of errors, returns errors in the order handlers were
+    /// called.
+    pub fn try_call<E, A>(&self, mut applicator: A) -> Vec<E>
+    where
+        A: FnMut(&F) -> Result<(), E>,
+    {
+        let mut errors = Vec::new();
+        self.call(|handler| {
+            if let Err(error) = applicator(handler) {
+                errors.push(error);
+            }
+        });
+
+        errors
+    }
+
+    /// Call applicator with each fallible handler until the first error and keep handlers in the
+    /// bag
+    ///
+    /// Remaining handlers are not called after the first error, which is returned.
+    pub fn try_call_stop<E, A>(&self, mut applicator: A) -> Result<(), E>
+    where
+        A: FnMut(&F) -> Result<(), E>,
+    {
+        match self.call_until(|handler| match applicator(handler) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(error) => ControlFlow::Break(error),
+        }) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Call applicator with each handler, catching panics, and keep handlers in the bag
+    ///
+    /// All handlers are called even if some of them panic, returns panic payloads in the order
+    /// handlers were called. Applicator and handlers are assumed to be unwind safe, see
+    /// [`std::panic::catch_unwind()`] for details.
+    #[cfg(feature = "std")]
+    pub fn call_catch_unwind<A>(&self, mut applicator: A) -> Vec<Box<dyn Any + Send>>
+    where
+        A: FnMut(&F),
+    {
+        let mut panics = Vec::new();
+        self.call(|handler| {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| applicator(handler))) {
+                panics.push(payload);
+            }
+        });
+
+        panics
+    }
+
+    /// Call applicator with each handler in parallel using [`rayon`]'s global thread pool and keep
+    /// handlers in the bag
+    ///
+    /// Unlike [`Bag::call()`] there is no guarantee about the order in which handlers are called,
+    /// returns number of handlers called once all of them have completed.
+    #[cfg(feature = "rayon")]
+    pub fn call_parallel<A>(&self, applicator: A) -> usize
+    where
+        A: Fn(&F) + Send + Sync,
+    {
+        let (handlers, collected) = self.handlers();
+        handlers.par_iter().for_each(|(_priority, index, handler)| {
+            collected.invoke(*index, || applicator(handler));
+        });
+
+        handlers.len()
+    }
+
+    /// Clones of handlers currently in the bag in the same order as in [`Bag::call()`], without
+    /// calling them
+    pub fn snapshot(&self) -> Vec<F> {
+        self.handlers()
+            .0
+            .into_iter()
+            .map(|(_priority, _index, handler)| handler)
+            .collect()
+    }
+
+    /// Indices of handlers currently in the bag and next index to be assigned, useful for
+    /// debugging dumps and comparing subscriptions across runs
+    #[cfg(feature = "serde")]
+    pub fn index_snapshot(&self) -> IndexSnapshot {
+        let inner = self.inner.read();
+        let mut indices = inner.handlers.keys().copied().collect::<Vec<_>>();
+        indices.sort_unstable();
+
+        IndexSnapshot {
+            indices,
+            next_index: inner.next_index,
+        }
+    }
+
+    /// Create independent bag that starts with clones of handlers currently in this bag
+    ///
+    /// Unlike [`Clone`], which creates another instance of the same bag, adding or removing
+    /// handlers in the fork doesn't affect this bag and vice versa. Handlers keep their priorities
+    /// and order, handler IDs of this bag only remove handlers from this bag. Fork has the same
+    /// limit as this bag, but not its name, hooks, middleware or deferred removal mode.
+    pub fn fork(&self) -> Self {
+        self.process_pending_removal();
+        let (handlers, next_index, limit) = {
+            let inner = self.inner.read();
+            (inner.handlers.clone(), inner.next_index, inner.limit)
+        };
+        let fork = Self::with_handlers(handlers, limit);
+        fork.inner.write().next_index = next_index;
+
+        fork
+    }
+
+    /// Create weak reference to the bag that doesn't keep handlers alive
+    pub fn downgrade(&self) -> WeakBag<F, A1, A2, A3, A4, A5, A6> {
+        WeakBag {
+            id: self.id,
+            inner: Arc::downgrade(&self.inner),
+            pending_removal: self.pending_removal.clone(),
+            a1: PhantomData,
+            a2: PhantomData,
+            a3: PhantomData,
+            a4: PhantomData,
+            a5: PhantomData,
+            a6: PhantomData,
+        }
+    }
+
+    /// Collect handlers with their priorities and indices in the order they should be called
+    /// together with state needed to call them
+    pub(crate) fn handlers(&self) -> (Handlers<F>, Collected) {
+        let mut handlers = SmallVec::new();
+        let collected = self.collect_handlers(&mut handlers);
+
+        (handlers, collected)
+    }
+
+    /// Replace contents of `handlers` with handlers, their priorities and indices in the order they
+    /// should be called
+    fn collect_handlers(&self, handlers: &mut SmallVec<[(i32, usize, F); 2]>) -> Collected {
+        handlers.clear();
+        self.process_pending_removal();
+        // We collect handlers first in order to avoid holding lock while calling handlers
+        let collected = {
+            let inner = self.inner.read();
+            inner.clone_handlers_into(handlers);
+            inner.collected()
+        };
+        sort_handlers(handlers);
+
+        collected
+    }
+
+    /// Call hook installed with [`Bag::on_change_during_call()`] if set of handlers is no longer
+    /// the same as when `version` was observed
+    #[cfg(feature = "debug-checks")]
+    fn check_version(&self, version: u64) {
+        let hook = {
+            let inner = self.inner.read();
+            if inner.version == version {
+                return;
+            }
+            inner.on_change_during_call.clone()
+        };
+        if let Some(hook) = hook {
+            hook();
+        }
+    }
+
+    /// Take all handlers out of the bag with their priorities and indices in the order they should
+    /// be called together with state needed to call them
+    fn take_handlers(&self) -> (Handlers<F>, Collected) {
+        self.process_pending_removal();
+        let (mut handlers, collected, hook) = {
+            let mut inner = self.inner.write();
+            if !inner.handlers.is_empty() {
+                inner.mark_changed();
+            }
+            // Draining instead of taking the whole map retains allocated capacity
+            let handlers = map::drain(&mut inner.handlers)
+                .map(|(index, (priority, handler))| (priority, index, handler))
+                .collect::<SmallVec<[(i32, usize, F); 2]>>();
+            (handlers, inner.collected(), inner.on_change.clone())
+        };
+        sort_handlers(&mut handlers);
+        for (_priority, index, _handler) in handlers.iter() {
+            notify(hook.clone(), ChangeKind::Removed, *index);
+        }
+
+        (handlers, collected)
+    }
+
+    /// Insert handler into the bag and return its index
+    fn insert(&self, callback: F, priority: i32) -> usize {
+        let (index, hook) = {
+            let mut inner = self.inner.write();
+            (inner.insert(callback, priority), inner.on_change.clone())
+        };
+        notify(hook, ChangeKind::Added, index);
+        self.process_pending_removal();
+
+        index
+    }
+
+    /// Remove handlers queued for removal by dropped handler IDs, does nothing unless bag was
+    /// created with [`Bag::with_deferred_removal()`]
+    ///
+    /// Must be called by every method that calls, takes or copies handlers before it looks at them.
+    fn process_pending_removal(&self) {
+        let pending_removal = match &self.pending_removal {
+            Some(pending_removal) => pending_removal,
+            None => return,
+        };
+        if pending_removal.lock().is_empty() {
+            return;
+        }
+        let (removed, hook) = {
+            let mut inner = self.inner.write();
+            // Queue is taken under bag's lock, so concurrent calls don't miss each other's removals
+            let indices = mem::take(&mut *pending_removal.lock());
+            let removed = indices
+                .iter()
+                .filter_map(|index| Some((*index, inner.handlers.remove(index)?)))
+                .collect::<SmallVec<[(usize, (i32, F)); 2]>>();
+            if !removed.is_empty() {
+                inner.mark_changed();
+            }
+            (removed, inner.on_change.clone())
+        };
+        // Handlers are dropped after lock is released
+        for (index, _handler) in removed {
+            notify(hook.clone(), ChangeKind::Removed, index);
+        }
+    }
+
+    /// Drop handlers queued for removal from already collected `handlers` without touching the
+    /// bag, for methods that must not wait for the write lock
+    fn skip_pending_removal<T>(&self, handlers: &mut SmallVec<[(i32, usize, T); 2]>) {
+        if let Some(pending_removal) = &self.pending_removal {
+            let pending_removal = pending_removal.lock();
+            if !pending_removal.is_empty() {
+                handlers.retain(|(_priority, index, _handler)| !pending_removal.contains(index));
+            }
+        }
+    }
+
+    /// Remove handler with specified index from the bag, returns `true` if it was in the bag
+    fn remove_index(inner: &RwLock<Inner<F>>, index: usize) -> bool {
+        let (handler, hook) = {
+            let mut inner = inner.write();
+            let handler = inner.handlers.remove(&index);
+            if handler.is_some() {
+                inner.mark_changed();
+            }
+            (handler, inner.on_change.clone())
+        };
+        // Handler is dropped after lock is released
+        let removed = handler.is_some();
+        drop(handler);
+        if removed {
+            notify(hook, ChangeKind::Removed, index);
+        }
+
+        removed
+    }
+
+    /// Closure that removes handler with specified index from the bag if bag still exists
+    fn remover(&self, index: usize) -> impl FnOnce() + Send + 'static {
+        let weak_inner = Arc::downgrade(&self.inner);
+        let pending_removal = self.pending_removal.as_ref().map(Arc::downgrade);
+
+        move || {
+            if let Some(pending_removal) = pending_removal {
+                // Only index is queued, so this never blocks on bag's lock
+                if let Some(pending_removal) = pending_removal.upgrade() {
+                    pending_removal.lock().push(index);
+                }
+            } else if let Some(inner) = weak_inner.upgrade() {
+                Self::remove_index(&inner, index);
+            }
+        }
+    }
+
+    /// Set index to be assigned to the next handler, allows testing index wraparound without
+    /// `usize::MAX` additions
+    #[cfg(test)]
+    fn set_next_index(&self, next_index: usize) {
+        self.inner.write().next_index = next_index;
+    }
+
+    fn with_handlers(handlers: Map<(i32, F)>, limit: Option<usize>) -> Self {
+        Self {
+            id: BagId::new(),
+            pending_removal: None,
+            inner: Arc::new(RwLock::new(Inner {
+                handlers,
+                next_index: 0,
+                limit,
+                on_change: None,
+                name: None,
+                middleware: None,
+                cursor: AtomicUsize::new(0),
+                #[cfg(feature = "debug-checks")]
+                version: 0,
+                #[cfg(feature = "debug-checks")]
+                on_change_during_call: None,
+            })),
+            a1: PhantomData,
+            a2: PhantomData,
+            a3: PhantomData,
+            a4: PhantomData,
+            a5: PhantomData,
+            a6: PhantomData,
+        }
+    }
+}
+
+impl<F, A1, A2, A3, A4, A5, A6> FromIterator<F> for Bag<F, A1, A2, A3, A4, A5, A6>
+where
+    F: Send + Sync + Clone + 'static,
+    A1: ?Sized,
+    A2: ?Sized,
+    A3: ?Sized,
+    A4: ?Sized,
+    A5: ?Sized,
+    A6: ?Sized,
+{
+    /// Create new bag with handlers added permanently, same as [`Bag::add_detached()`]
+    fn from_iter<I>(callbacks: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+    {
+        let bag = Self::default();
+        {
+            let mut inner = bag.inner.write();
+            for callback in callbacks {
+                inner.insert(callback, 0);
+            }
+        }
+
+        bag
+    }
+}
+
+/// Reusable buffer for collecting handlers in [`Bag::call_with_buffer()`]
+pub struct DispatchBuffer<F> {
+    handlers: SmallVec<[(i32, usize, F); 2]>,
+}
+
+impl<F> fmt::Debug for DispatchBuffer<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DispatchBuffer")
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl<F> Default for DispatchBuffer<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F> DispatchBuffer<F> {
+    /// Create new empty buffer
+    pub fn new() -> Self {
+        Self {
+            handlers: SmallVec::new(),
+        }
+    }
+
+    /// Create new buffer with space for at least `capacity` handlers preallocated
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            handlers: SmallVec::with_capacity(capacity),
+        }
+    }
+
+    /// Number of handlers the buffer can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.handlers.capacity()
+    }
+}
+
+/// Weak reference to a [`Bag`] that doesn't keep handlers alive, obtained with
+/// [`Bag::downgrade()`]
+///
+/// Useful for accessing the bag from within its own handlers without creating a reference cycle.
+pub struct WeakBag<
+    F: Send + Sync + Clone + 'static,
+    A1: ?Sized = private::Private,
+    A2: ?Sized = private::Private,
+    A3: ?Sized = private::Private,
+    A4: ?Sized = private::Private,
+    A5: ?Sized = private::Private,
+    A6: ?Sized = private::Private,
 > {
-    inner: Arc<Mutex<Inner<F>>>,
+    id: BagId,
+    inner: Weak<RwLock<Inner<F>>>,
+    pending_removal: Option<Arc<Mutex<Vec<usize>>>>,
     a1: PhantomData<A1>,
     a2: PhantomData<A2>,
     a3: PhantomData<A3>,
     a4: PhantomData<A4>,
     a5: PhantomData<A5>,
+    a6: PhantomData<A6>,
 }
 
-impl<F, A1, A2, A3, A4, A5> fmt::Debug for Bag<F, A1, A2, A3, A4, A5>
+impl<F, A1, A2, A3, A4, A5, A6> fmt::Debug for WeakBag<F, A1, A2, A3, A4, A5, A6>
 where
     F: Send + Sync + Clone + 'static,
     A1: ?Sized,
@@ -44,13 +1605,14 @@ where
     A3: ?Sized,
     A4: ?Sized,
     A5: ?Sized,
+    A6: ?Sized,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Bag").finish()
+        f.debug_struct("WeakBag").finish()
     }
 }
 
-impl<F, A1, A2, A3, A4, A5> Clone for Bag<F, A1, A2, A3, A4, A5>
+impl<F, A1, A2, A3, A4, A5, A6> Clone for WeakBag<F, A1, A2, A3, A4, A5, A6>
 where
     F: Send + Sync + Clone + 'static,
     A1: ?Sized,
@@ -58,20 +1620,24 @@ where
     A3: ?Sized,
     A4: ?Sized,
     A5: ?Sized,
+    A6: ?Sized,
 {
     fn clone(&self) -> Self {
         Self {
-            inner: Arc::clone(&self.inner),
-            a1: PhantomData::default(),
-            a2: PhantomData::default(),
-            a3: PhantomData::default(),
-            a4: PhantomData::default(),
-            a5: PhantomData::default(),
+            id: self.id,
+            inner: Weak::clone(&self.inner),
+            pending_removal: self.pending_removal.clone(),
+            a1: PhantomData,
+            a2: PhantomData,
+            a3: PhantomData,
+            a4: PhantomData,
+            a5: PhantomData,
+            a6: PhantomData,
         }
     }
 }
 
-impl<F, A1, A2, A3, A4, A5> Default for Bag<F, A1, A2, A3, A4, A5>
+impl<F, A1, A2, A3, A4, A5, A6> WeakBag<F, A1, A2, A3, A4, A5, A6>
 where
     F: Send + Sync + Clone + 'static,
     A1: ?Sized,
@@ -79,84 +1645,70 @@ where
     A3: ?Sized,
     A4: ?Sized,
     A5: ?Sized,
+    A6: ?Sized,
 {
-    fn default() -> Self {
-        Self {
-            inner: Arc::new(Mutex::new(Inner {
-                handlers: IntMap::default(),
-                next_index: 0,
-            })),
-            a1: PhantomData::default(),
-            a2: PhantomData::default(),
-            a3: PhantomData::default(),
-            a4: PhantomData::default(),
-            a5: PhantomData::default(),
-        }
+    /// Get [`Bag`] back if it still exists, returns `None` if all instances of the bag were dropped
+    pub fn upgrade(&self) -> Option<Bag<F, A1, A2, A3, A4, A5, A6>> {
+        self.inner.upgrade().map(|inner| Bag {
+            id: self.id,
+            inner,
+            pending_removal: self.pending_removal.clone(),
+            a1: PhantomData,
+            a2: PhantomData,
+            a3: PhantomData,
+            a4: PhantomData,
+            a5: PhantomData,
+            a6: PhantomData,
+        })
     }
 }
 
-impl<F, A1, A2, A3, A4, A5> Bag<F, A1, A2, A3, A4, A5>
+impl<T, A1, A2, A3, A4, A5, A6> Bag<Arc<T>, A1, A2, A3, A4, A5, A6>
 where
-    F: Send + Sync + Clone + 'static,
+    T: Send + Sync + ?Sized + 'static,
     A1: ?Sized,
     A2: ?Sized,
     A3: ?Sized,
     A4: ?Sized,
     A5: ?Sized,
+    A6: ?Sized,
 {
-    /// Add new event handler to a bag
-    pub fn add(&self, callback: F) -> HandlerId {
-        let index;
-
-        {
-            let mut inner = self.inner.lock();
-
-            index = loop {
-                let index = inner.next_index;
-                inner.next_index += 1;
+    /// Add new event handler to a bag with default priority `0` unless the same handler is already
+    /// in the bag, returns `None` in such case
+    ///
+    /// Handlers are considered the same if they point to the same allocation.
+    pub fn add_unique(&self, callback: Arc<T>) -> Option<HandlerId> {
+        self.process_pending_removal();
+        let (index, hook) = {
+            let mut inner = self.inner.write();
 
-                if !inner.handlers.contains_key(&index) {
-                    inner.handlers.insert(index, callback);
-                    break index;
-                }
+            if inner
+                .handlers
+                .values()
+                .any(|(_priority, handler)| Arc::ptr_eq(handler, &callback))
+            {
+                return None;
             }
-        }
 
-        HandlerId::new({
-            let weak_inner = Arc::downgrade(&self.inner);
-
-            move || {
-                if let Some(inner) = weak_inner.upgrade() {
-                    inner.lock().handlers.remove(&index);
-                }
-            }
-        })
-    }
+            (inner.insert(callback, 0), inner.on_change.clone())
+        };
+        notify(hook, ChangeKind::Added, index);
 
-    /// Call applicator with each handler and keep handlers in the bag
-    pub fn call<A>(&self, applicator: A)
-    where
-        A: Fn(&F),
-    {
-        // We collect handlers first in order to avoid holding lock while calling handlers
-        let handlers = self
-            .inner
-            .lock()
-            .handlers
-            .values()
-            .cloned()
-            .collect::<SmallVec<[F; 2]>>();
-        for handler in handlers.iter() {
-            applicator(handler);
-        }
+        Some(HandlerId::new(self.id, index, self.remover(index)))
     }
 }
 
 impl<F: Fn() + Send + Sync + ?Sized + 'static> Bag<Arc<F>> {
-    /// Call each handler without arguments and keep handlers in the bag
-    pub fn call_simple(&self) {
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self) -> usize {
         self.call(|handler| handler())
     }
+
+    /// Call each handler and remove handlers from the bag, returns number of handlers called
+    pub fn call_once_simple(&self) -> usize {
+        self.call_once(|handler| handler())
+    }
 }
 
 impl<A1, F> Bag<Arc<F>, A1>
@@ -164,10 +1716,16 @@ where
     A1: Sized,
     F: Fn(&A1) + Send + Sync + ?Sized + 'static,
 {
-    /// Call each handler without arguments and keep handlers in the bag
-    pub fn call_simple(&self, a1: &A1) {
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1) -> usize {
         self.call(|handler| handler(a1))
     }
+
+    /// Call each handler and remove handlers from the bag, returns number of handlers called
+    pub fn call_once_simple(&self, a1: &A1) -> usize {
+        self.call_once(|handler| handler(a1))
+    }
 }
 
 impl<A1, A2, F> Bag<Arc<F>, A1, A2>
@@ -176,10 +1734,16 @@ where
     A2: Sized,
     F: Fn(&A1, &A2) + Send + Sync + ?Sized + 'static,
 {
-    /// Call each handler without arguments and keep handlers in the bag
-    pub fn call_simple(&self, a1: &A1, a2: &A2) {
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2) -> usize {
         self.call(|handler| handler(a1, a2))
     }
+
+    /// Call each handler and remove handlers from the bag, returns number of handlers called
+    pub fn call_once_simple(&self, a1: &A1, a2: &A2) -> usize {
+        self.call_once(|handler| handler(a1, a2))
+    }
 }
 
 impl<A1, A2, A3, F> Bag<Arc<F>, A1, A2, A3>
@@ -189,10 +1753,16 @@ where
     A3: Sized,
     F: Fn(&A1, &A2, &A3) + Send + Sync + ?Sized + 'static,
 {
-    /// Call each handler without arguments and keep handlers in the bag
-    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3) {
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3) -> usize {
         self.call(|handler| handler(a1, a2, a3))
     }
+
+    /// Call each handler and remove handlers from the bag, returns number of handlers called
+    pub fn call_once_simple(&self, a1: &A1, a2: &A2, a3: &A3) -> usize {
+        self.call_once(|handler| handler(a1, a2, a3))
+    }
 }
 
 impl<A1, A2, A3, A4, F> Bag<Arc<F>, A1, A2, A3, A4>
@@ -203,10 +1773,16 @@ where
     A4: Sized,
     F: Fn(&A1, &A2, &A3, &A4) + Send + Sync + ?Sized + 'static,
 {
-    /// Call each handler without arguments and keep handlers in the bag
-    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4) {
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4) -> usize {
         self.call(|handler| handler(a1, a2, a3, a4))
     }
+
+    /// Call each handler and remove handlers from the bag, returns number of handlers called
+    pub fn call_once_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4) -> usize {
+        self.call_once(|handler| handler(a1, a2, a3, a4))
+    }
 }
 
 impl<A1, A2, A3, A4, A5, F> Bag<Arc<F>, A1, A2, A3, A4, A5>
@@ -218,8 +1794,242 @@ where
     A5: Sized,
     F: Fn(&A1, &A2, &A3, &A4, &A5) + Send + Sync + ?Sized + 'static,
 {
-    /// Call each handler without arguments and keep handlers in the bag
-    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5) {
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5) -> usize {
         self.call(|handler| handler(a1, a2, a3, a4, a5))
     }
+
+    /// Call each handler and remove handlers from the bag, returns number of handlers called
+    pub fn call_once_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5) -> usize {
+        self.call_once(|handler| handler(a1, a2, a3, a4, a5))
+    }
+}
+
+impl<A1, A2, A3, A4, A5, A6, F> Bag<Arc<F>, A1, A2, A3, A4, A5, A6>
+where
+    A1: Sized,
+    A2: Sized,
+    A3: Sized,
+    A4: Sized,
+    A5: Sized,
+    A6: Sized,
+    F: Fn(&A1, &A2, &A3, &A4, &A5, &A6) + Send + Sync + ?Sized + 'static,
+{
+    /// Call each handler without arguments and keep handlers in the bag, returns number of handlers
+    /// called
+    pub fn call_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5, a6: &A6) -> usize {
+        self.call(|handler| handler(a1, a2, a3, a4, a5, a6))
+    }
+
+    /// Call each handler and remove handlers from the bag, returns number of handlers called
+    pub fn call_once_simple(&self, a1: &A1, a2: &A2, a3: &A3, a4: &A4, a5: &A5, a6: &A6) -> usize {
+        self.call_once(|handler| handler(a1, a2, a3, a4, a5, a6))
+    }
+}
+
+impl<A1, F> Bag<Arc<F>, A1>
+where
+    A1: Sized + Clone,
+    F: Fn(A1) + Send + Sync + ?Sized + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and keep handlers in the bag,
+    /// returns number of handlers called
+    ///
+    /// With `A1 = Arc<T>` each handler gets its own clone of `Arc`, which is handy for sharing
+    /// large immutable data with handlers that keep it.
+    pub fn call_simple_owned(&self, a1: A1) -> usize {
+        self.call(|handler| handler(a1.clone()))
+    }
+}
+
+impl<A1, A2, F> Bag<Arc<F>, A1, A2>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    F: Fn(A1, A2) + Send + Sync + ?Sized + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and keep handlers in the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2) -> usize {
+        self.call(|handler| handler(a1.clone(), a2.clone()))
+    }
+}
+
+impl<A1, A2, A3, F> Bag<Arc<F>, A1, A2, A3>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    A3: Sized + Clone,
+    F: Fn(A1, A2, A3) + Send + Sync + ?Sized + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and keep handlers in the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2, a3: A3) -> usize {
+        self.call(|handler| handler(a1.clone(), a2.clone(), a3.clone()))
+    }
+}
+
+impl<A1, A2, A3, A4, F> Bag<Arc<F>, A1, A2, A3, A4>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    A3: Sized + Clone,
+    A4: Sized + Clone,
+    F: Fn(A1, A2, A3, A4) + Send + Sync + ?Sized + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and keep handlers in the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2, a3: A3, a4: A4) -> usize {
+        self.call(|handler| handler(a1.clone(), a2.clone(), a3.clone(), a4.clone()))
+    }
+}
+
+impl<A1, A2, A3, A4, A5, F> Bag<Arc<F>, A1, A2, A3, A4, A5>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    A3: Sized + Clone,
+    A4: Sized + Clone,
+    A5: Sized + Clone,
+    F: Fn(A1, A2, A3, A4, A5) + Send + Sync + ?Sized + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and keep handlers in the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2, a3: A3, a4: A4, a5: A5) -> usize {
+        self.call(|handler| handler(a1.clone(), a2.clone(), a3.clone(), a4.clone(), a5.clone()))
+    }
+}
+
+impl<A1, A2, A3, A4, A5, A6, F> Bag<Arc<F>, A1, A2, A3, A4, A5, A6>
+where
+    A1: Sized + Clone,
+    A2: Sized + Clone,
+    A3: Sized + Clone,
+    A4: Sized + Clone,
+    A5: Sized + Clone,
+    A6: Sized + Clone,
+    F: Fn(A1, A2, A3, A4, A5, A6) + Send + Sync + ?Sized + 'static,
+{
+    /// Call each handler with clones of arguments passed by value and keep handlers in the bag,
+    /// returns number of handlers called
+    pub fn call_simple_owned(&self, a1: A1, a2: A2, a3: A3, a4: A4, a5: A5, a6: A6) -> usize {
+        self.call(|handler| {
+            handler(
+                a1.clone(),
+                a2.clone(),
+                a3.clone(),
+                a4.clone(),
+                a5.clone(),
+                a6.clone(),
+            )
+        })
+    }
+}
+
+impl<A1, F> Bag<Arc<F>, A1>
+where
+    A1: Sized,
+    F: Fn(&mut A1) + Send + Sync + ?Sized + 'static,
+{
+    /// Call each handler with exclusive reference to the argument one after another and keep
+    /// handlers in the bag, returns number of handlers called
+    ///
+    /// Handlers are called in the same order as in [`Bag::call()`], each handler observes
+    /// modifications made by handlers called before it.
+    pub fn call_simple_mut(&self, a1: &mut A1) -> usize {
+        self.call(|handler| handler(a1))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Bag<Arc<dyn Fn(&T) + Send + Sync + 'static>, T>
+where
+    T: Clone + Send + 'static,
+{
+    /// Add event handler that forwards clone of each event to the channel
+    ///
+    /// Events are silently dropped once receiver is gone, drop returned handler ID to stop
+    /// forwarding.
+    pub fn forward_to(&self, sender: mpsc::Sender<T>) -> HandlerId {
+        self.add(Arc::new(move |event: &T| {
+            let _ = sender.send(event.clone());
+        }))
+    }
+
+    /// Subscribe to events as a stream of clones of each event
+    ///
+    /// Dropping either returned stream or handler ID removes the handler from the bag, stream ends
+    /// once handler is removed.
+    #[cfg(feature = "futures")]
+    pub fn subscribe_stream(&self) -> (EventStream<T>, HandlerId) {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let index = self.insert(
+            Arc::new(move |event: &T| {
+                let _ = sender.unbounded_send(event.clone());
+            }),
+            0,
+        );
+
+        (
+            EventStream::new(receiver, self.remover(index)),
+            HandlerId::new(self.id, index, self.remover(index)),
+        )
+    }
+
+    /// Subscribe to events as a stream of clones of each event that holds at most `capacity`
+    /// events, `policy` decides what happens with new events when the stream is full
+    ///
+    /// Capacity of `0` is treated as `1`. With [`Overflow::Block`] the thread calling handlers is
+    /// blocked until the stream is polled, so the stream must not be consumed on that same thread.
+    /// Dropping either returned stream or handler ID removes the handler from the bag, stream ends
+    /// once handler is removed.
+    #[cfg(feature = "futures")]
+    pub fn subscribe_stream_bounded(
+        &self,
+        capacity: usize,
+        policy: Overflow,
+    ) -> (EventStream<T>, HandlerId) {
+        let (sender, mut stream) = EventStream::bounded(capacity.max(1), policy);
+        let index = self.insert(
+            Arc::new(move |event: &T| {
+                sender.send(event.clone());
+            }),
+            0,
+        );
+        stream.set_remove(self.remover(index));
+
+        (stream, HandlerId::new(self.id, index, self.remover(index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_wraparound() {
+        let bag = Bag::<Arc<dyn Fn() -> usize + Send + Sync + 'static>>::default();
+        bag.set_next_index(usize::MAX - 1);
+
+        let handler_ids = (1..=3)
+            .map(|value| bag.add(Arc::new(move || value)))
+            .collect::<Vec<_>>();
+        let indices = handler_ids
+            .iter()
+            .map(|handler_id| handler_id.index().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [usize::MAX - 1, usize::MAX, 0]);
+
+        // Indices of handlers that are still in the bag are skipped
+        bag.set_next_index(usize::MAX);
+        let handler_id = bag.add(Arc::new(|| 4));
+        assert_eq!(handler_id.index(), Some(1));
+
+        // Handlers added after wraparound are called first
+        assert_eq!(bag.call_collect(|handler| handler()), [3, 4, 1, 2]);
+
+        drop(handler_ids);
+        assert_eq!(bag.call_collect(|handler| handler()), [4]);
+    }
 }
@@ -0,0 +1,220 @@
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::{fmt, mem};
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::stream::{FusedStream, Stream};
+use futures::task::AtomicWaker;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+/// What happens with a new event when stream created with
+/// [`Bag::subscribe_stream_bounded()`](crate::Bag::subscribe_stream_bounded) is full
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Overflow {
+    /// Oldest event in the stream is dropped to make space for the new one
+    DropOldest,
+    /// New event is dropped
+    DropNewest,
+    /// Thread that calls handlers is blocked until the consumer makes space for the new event or
+    /// stream is dropped
+    Block,
+}
+
+struct BoundedState<T> {
+    queue: VecDeque<T>,
+    /// Stream was dropped
+    closed: bool,
+    /// Handler was removed from the bag, no more events will arrive
+    finished: bool,
+}
+
+struct Bounded<T> {
+    state: Mutex<BoundedState<T>>,
+    capacity: usize,
+    policy: Overflow,
+    not_full: Condvar,
+    waker: AtomicWaker,
+}
+
+impl<T> Bounded<T> {
+    fn lock(&self) -> MutexGuard<'_, BoundedState<T>> {
+        // State is consistent after every operation, so poisoning can be ignored
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Sending half of a bounded stream, stream ends once it is dropped
+pub(crate) struct BoundedSender<T> {
+    shared: Arc<Bounded<T>>,
+}
+
+impl<T> Drop for BoundedSender<T> {
+    fn drop(&mut self) {
+        self.shared.lock().finished = true;
+        self.shared.waker.wake();
+    }
+}
+
+impl<T> BoundedSender<T> {
+    pub(crate) fn send(&self, event: T) {
+        let shared = &self.shared;
+        let mut state = shared.lock();
+        if state.queue.len() >= shared.capacity {
+            match shared.policy {
+                Overflow::DropOldest => {
+                    state.queue.pop_front();
+                }
+                Overflow::DropNewest => {
+                    return;
+                }
+                Overflow::Block => {
+                    while state.queue.len() >= shared.capacity && !state.closed {
+                        state = shared
+                            .not_full
+                            .wait(state)
+                            .unwrap_or_else(PoisonError::into_inner);
+                    }
+                }
+            }
+        }
+        if state.closed {
+            return;
+        }
+        state.queue.push_back(event);
+        drop(state);
+        shared.waker.wake();
+    }
+}
+
+enum Receiver<T> {
+    Unbounded(UnboundedReceiver<T>),
+    Bounded(Arc<Bounded<T>>),
+}
+
+/// Stream of events produced by [`Bag::subscribe_stream()`](crate::Bag::subscribe_stream) or
+/// [`Bag::subscribe_stream_bounded()`](crate::Bag::subscribe_stream_bounded)
+///
+/// Ends once corresponding handler is removed from the bag, dropping the stream removes the
+/// handler from the bag.
+pub struct EventStream<T> {
+    receiver: Receiver<T>,
+    remove: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl<T> fmt::Debug for EventStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventStream").finish()
+    }
+}
+
+impl<T> Drop for EventStream<T> {
+    fn drop(&mut self) {
+        if let Receiver::Bounded(shared) = &self.receiver {
+            // Unblock senders before removing the handler, which may be blocked in `send()`
+            let events = {
+                let mut state = shared.lock();
+                state.closed = true;
+                mem::take(&mut state.queue)
+            };
+            shared.not_full.notify_all();
+            drop(events);
+        }
+        if let Some(remove) = self.remove.take() {
+            remove();
+        }
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match &mut self.receiver {
+            Receiver::Unbounded(receiver) => Pin::new(receiver).poll_next(cx),
+            Receiver::Bounded(shared) => {
+                // Waker is registered before checking the queue, so no wake up is missed
+                shared.waker.register(cx.waker());
+                let mut state = shared.lock();
+                match state.queue.pop_front() {
+                    Some(event) => {
+                        drop(state);
+                        shared.not_full.notify_one();
+                        Poll::Ready(Some(event))
+                    }
+                    None if state.finished => Poll::Ready(None),
+                    None => Poll::Pending,
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.receiver {
+            Receiver::Unbounded(receiver) => receiver.size_hint(),
+            Receiver::Bounded(shared) => {
+                let state = shared.lock();
+                let len = state.queue.len();
+                (len, state.finished.then_some(len))
+            }
+        }
+    }
+}
+
+impl<T> FusedStream for EventStream<T> {
+    fn is_terminated(&self) -> bool {
+        match &self.receiver {
+            Receiver::Unbounded(receiver) => receiver.is_terminated(),
+            Receiver::Bounded(shared) => {
+                let state = shared.lock();
+                state.finished && state.queue.is_empty()
+            }
+        }
+    }
+}
+
+impl<T> EventStream<T> {
+    pub(crate) fn new<R>(receiver: UnboundedReceiver<T>, remove: R) -> Self
+    where
+        R: FnOnce() + Send + 'static,
+    {
+        Self {
+            receiver: Receiver::Unbounded(receiver),
+            remove: Some(Box::new(remove)),
+        }
+    }
+
+    /// Create sending half of a bounded stream, remover is set afterwards with
+    /// [`EventStream::set_remove()`]
+    pub(crate) fn bounded(capacity: usize, policy: Overflow) -> (BoundedSender<T>, Self) {
+        let shared = Arc::new(Bounded {
+            state: Mutex::new(BoundedState {
+                queue: VecDeque::with_capacity(capacity),
+                closed: false,
+                finished: false,
+            }),
+            capacity,
+            policy,
+            not_full: Condvar::new(),
+            waker: AtomicWaker::new(),
+        });
+
+        (
+            BoundedSender {
+                shared: Arc::clone(&shared),
+            },
+            Self {
+                receiver: Receiver::Bounded(shared),
+                remove: None,
+            },
+        )
+    }
+
+    pub(crate) fn set_remove<R>(&mut self, remove: R)
+    where
+        R: FnOnce() + Send + 'static,
+    {
+        self.remove.replace(Box::new(remove));
+    }
+}
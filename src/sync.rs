@@ -0,0 +1,134 @@
+//! Synchronization primitives used internally, backed by `parking_lot` when corresponding feature
+//! is enabled, by the standard library when only `std` feature is enabled and by `spin` in
+//! `no_std` environments
+//!
+//! Lock is selected with features rather than a type parameter of bags on purpose: bags already
+//! have a type parameter for handlers and six for arguments, another one would leak into every
+//! signature of downstream code, while `std::sync::Mutex` doesn't implement `lock_api::RawMutex`
+//! anyway. Bags never hold the lock while calling handlers (except [`Bag::call_locked()`]), so the
+//! choice of lock is not observable beyond performance, except for `Bag::call_timeout()` and
+//! `Timeout` error that rely on timed locking and are only available with `parking_lot` feature.
+//!
+//! [`Bag::call_locked()`]: crate::Bag::call_locked
+
+#[cfg(feature = "parking_lot")]
+pub(crate) use parking_lot::{Mutex, RwLock};
+#[cfg(all(feature = "spin", not(feature = "std")))]
+pub(crate) use spin::{Mutex, RwLock};
+#[cfg(all(feature = "std", not(feature = "parking_lot")))]
+pub(crate) use std_sync::{Mutex, RwLock};
+#[cfg(not(any(feature = "std", feature = "spin")))]
+pub(crate) use unavailable::{Mutex, RwLock};
+
+#[cfg(all(feature = "std", not(feature = "parking_lot")))]
+mod std_sync {
+    use std::sync::{MutexGuard, PoisonError, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+    /// Thin wrapper around [`std::sync::Mutex`] with API of `parking_lot::Mutex`
+    pub(crate) struct Mutex<T>(std::sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Self(std::sync::Mutex::new(value))
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            // Critical sections never leave data in inconsistent state, so poisoning is ignored
+            self.0.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            match self.0.try_lock() {
+                Ok(guard) => Some(guard),
+                Err(TryLockError::Poisoned(error)) => Some(error.into_inner()),
+                Err(TryLockError::WouldBlock) => None,
+            }
+        }
+    }
+    /// Thin wrapper around [`std::sync::RwLock`] with API of `parking_lot::RwLock`
+    pub(crate) struct RwLock<T>(std::sync::RwLock<T>);
+
+    // Critical sections never leave data in inconsistent state, so poisoning is ignored
+    impl<T> RwLock<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Self(std::sync::RwLock::new(value))
+        }
+
+        pub(crate) fn read(&self) -> RwLockReadGuard<'_, T> {
+            self.0.read().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub(crate) fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+            match self.0.try_read() {
+                Ok(guard) => Some(guard),
+                Err(TryLockError::Poisoned(error)) => Some(error.into_inner()),
+                Err(TryLockError::WouldBlock) => None,
+            }
+        }
+
+        pub(crate) fn write(&self) -> RwLockWriteGuard<'_, T> {
+            self.0.write().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+}
+
+/// Placeholders used when neither `std` nor `spin` feature is enabled, such that `compile_error!`
+/// in crate root is the only error reported instead of being buried under type errors
+#[cfg(not(any(feature = "std", feature = "spin")))]
+mod unavailable {
+    use core::marker::PhantomData;
+    use core::ops::{Deref, DerefMut};
+
+    // Never constructed into a working binary, so thread safety of placeholders doesn't matter
+    pub(crate) struct Mutex<T>(PhantomData<fn() -> T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(_value: T) -> Self {
+            Self(PhantomData)
+        }
+
+        pub(crate) fn lock(&self) -> Guard<'_, T> {
+            Guard(PhantomData)
+        }
+
+        pub(crate) fn try_lock(&self) -> Option<Guard<'_, T>> {
+            None
+        }
+    }
+
+    pub(crate) struct RwLock<T>(PhantomData<fn() -> T>);
+
+    impl<T> RwLock<T> {
+        pub(crate) fn new(_value: T) -> Self {
+            Self(PhantomData)
+        }
+
+        pub(crate) fn read(&self) -> Guard<'_, T> {
+            Guard(PhantomData)
+        }
+
+        pub(crate) fn try_read(&self) -> Option<Guard<'_, T>> {
+            None
+        }
+
+        pub(crate) fn write(&self) -> Guard<'_, T> {
+            Guard(PhantomData)
+        }
+    }
+
+    pub(crate) struct Guard<'a, T>(PhantomData<fn() -> &'a mut T>);
+
+    impl<T> Deref for Guard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            unreachable!("Either `std` or `spin` feature must be enabled")
+        }
+    }
+
+    impl<T> DerefMut for Guard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            unreachable!("Either `std` or `spin` feature must be enabled")
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::HandlerId;
+use core::ops::Deref;
+
+/// [`HandlerId`] with user-provided data attached, for example name of the subscriber
+///
+/// Dereferences to [`HandlerId`], once dropped handler will be removed automatically just like
+/// with [`HandlerId`] itself.
+#[must_use = "Handler will be unregistered immediately if not used"]
+#[derive(Debug, Clone)]
+pub struct TaggedHandlerId<D> {
+    handler_id: HandlerId,
+    data: D,
+}
+
+impl<D> Deref for TaggedHandlerId<D> {
+    type Target = HandlerId;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handler_id
+    }
+}
+
+impl<D> TaggedHandlerId<D> {
+    /// Attach data to handler ID
+    pub fn new(handler_id: HandlerId, data: D) -> Self {
+        Self { handler_id, data }
+    }
+
+    /// Data attached to handler ID
+    pub fn data(&self) -> &D {
+        &self.data
+    }
+
+    /// Exclusive reference to data attached to handler ID
+    pub fn data_mut(&mut self) -> &mut D {
+        &mut self.data
+    }
+
+    /// Split into handler ID and attached data
+    pub fn into_parts(self) -> (HandlerId, D) {
+        (self.handler_id, self.data)
+    }
+}
@@ -0,0 +1,89 @@
+mod async_bag {
+    use event_listener_primitives::AsyncBag;
+    use futures::executor::block_on;
+    use futures::future::BoxFuture;
+    use parking_lot::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn sequential() {
+        let bag =
+            AsyncBag::<Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync + 'static>>::default();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+
+        let handler_ids = (0..3)
+            .map(|i| {
+                let calls = Arc::clone(&calls);
+                bag.add(Arc::new(move || {
+                    let calls = Arc::clone(&calls);
+                    Box::pin(async move {
+                        calls.lock().push(i);
+                    })
+                }))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(block_on(bag.call_async()), 3);
+        assert_eq!(*calls.lock(), vec![0, 1, 2]);
+
+        drop(handler_ids);
+
+        assert_eq!(block_on(bag.call_async()), 0);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn with_applicator() {
+        let bag =
+            AsyncBag::<Arc<dyn Fn(i32) -> BoxFuture<'static, ()> + Send + Sync + 'static>>::default(
+            );
+        let sum = Arc::new(AtomicUsize::new(0));
+
+        {
+            let sum = Arc::clone(&sum);
+            bag.add(Arc::new(move |a1| {
+                let sum = Arc::clone(&sum);
+                Box::pin(async move {
+                    sum.fetch_add(a1 as usize, Ordering::SeqCst);
+                })
+            }))
+            .detach();
+        }
+
+        block_on(bag.call(|handler| handler(5)));
+
+        assert_eq!(sum.load(Ordering::SeqCst), 5);
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn concurrent() {
+        let bag =
+            AsyncBag::<Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync + 'static>>::default();
+        let (tx, rx) = futures::channel::oneshot::channel::<()>();
+        let tx = Arc::new(Mutex::new(Some(tx)));
+        let rx = futures::future::FutureExt::shared(rx);
+
+        // First handler waits for the second one, which only works when futures are polled
+        // concurrently
+        bag.add(Arc::new(move || {
+            let rx = rx.clone();
+            Box::pin(async move {
+                rx.await.unwrap();
+            })
+        }))
+        .detach();
+        bag.add(Arc::new(move || {
+            let tx = tx.lock().take();
+            Box::pin(async move {
+                if let Some(tx) = tx {
+                    tx.send(()).unwrap();
+                }
+            })
+        }))
+        .detach();
+
+        assert_eq!(block_on(bag.call_async_concurrent()), 2);
+    }
+}
@@ -0,0 +1,57 @@
+mod callable {
+    use event_listener_primitives::{Bag, BagOnce, Callable, HandlerId};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn subscribe<B, F>(bag: &B, callback: F) -> HandlerId
+    where
+        B: Callable<F>,
+    {
+        bag.add(callback)
+    }
+
+    fn fire<B, F>(bag: &B) -> usize
+    where
+        B: Callable<F>,
+        F: FnOnce(),
+    {
+        Callable::call(bag, |handler| handler())
+    }
+
+    #[test]
+    fn bag_and_bag_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        {
+            let calls = Arc::clone(&calls);
+            subscribe(
+                &bag,
+                Arc::new(move || {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                }) as Arc<dyn Fn() + Send + Sync + 'static>,
+            )
+            .detach();
+        }
+
+        assert_eq!(Callable::call(&bag, |handler| handler()), 1);
+        assert_eq!(Callable::call(&bag, |handler| handler()), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let bag_once = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+        {
+            let calls = Arc::clone(&calls);
+            subscribe(
+                &bag_once,
+                Box::new(move || {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                }) as Box<dyn FnOnce() + Send + 'static>,
+            )
+            .detach();
+        }
+
+        assert_eq!(fire(&bag_once), 1);
+        assert_eq!(fire(&bag_once), 0);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,59 @@
+mod handler_group {
+    use event_listener_primitives::{Bag, HandlerGroup};
+    use std::sync::Arc;
+
+    #[test]
+    fn drop_removes_all() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let other_bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let mut group = HandlerGroup::new();
+        assert!(group.is_empty());
+        group.push(bag.add(Arc::new(|| {})));
+        group.extend(vec![
+            bag.add(Arc::new(|| {})),
+            other_bag.add(Arc::new(|| {})),
+        ]);
+        assert_eq!(group.len(), 3);
+        assert_eq!(bag.len(), 2);
+        assert_eq!(other_bag.len(), 1);
+
+        drop(group);
+
+        assert!(bag.is_empty());
+        assert!(other_bag.is_empty());
+    }
+
+    #[test]
+    fn detach_all() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let mut group = (0..2)
+            .map(|_| bag.add(Arc::new(|| {})))
+            .collect::<HandlerGroup>();
+        group.detach_all();
+        group.push(bag.add(Arc::new(|| {})));
+        assert_eq!(bag.len(), 3);
+
+        drop(group);
+
+        assert_eq!(bag.len(), 2);
+    }
+
+    #[test]
+    fn remove_all() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let group = (0..2)
+            .map(|_| bag.add(Arc::new(|| {})))
+            .collect::<HandlerGroup>();
+        group.remove_all();
+        assert!(bag.is_empty());
+        assert_eq!(group.len(), 2);
+
+        bag.add_detached(Arc::new(|| {}));
+        drop(group);
+
+        assert_eq!(bag.len(), 1);
+    }
+}
@@ -0,0 +1,198 @@
+mod handler_id {
+    use event_listener_primitives::{Bag, BagOnce, HandlerId};
+    use std::sync::Arc;
+
+    #[test]
+    fn merge() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let other_bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let bag_once = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+
+        let handler_id = HandlerId::merge(vec![
+            bag.add(Arc::new(|| {})),
+            other_bag.add(Arc::new(|| {})),
+            bag_once.add(Box::new(|| {})),
+        ]);
+
+        assert_eq!(handler_id.index(), None);
+        assert!(!bag.remove(&handler_id));
+        assert_eq!(bag.len(), 1);
+        assert_eq!(other_bag.len(), 1);
+        assert_eq!(bag_once.len(), 1);
+
+        drop(handler_id);
+
+        assert!(bag.is_empty());
+        assert!(other_bag.is_empty());
+        assert!(bag_once.is_empty());
+    }
+
+    #[test]
+    fn merge_detach() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        HandlerId::merge(vec![bag.add(Arc::new(|| {})), bag.add(Arc::new(|| {}))]).detach();
+
+        assert_eq!(bag.len(), 2);
+    }
+
+    #[test]
+    fn remove_now() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let handler_id = bag.add(Arc::new(|| {}));
+
+        assert!(handler_id.is_attached());
+
+        handler_id.remove_now();
+
+        assert!(!handler_id.is_attached());
+        assert!(bag.is_empty());
+
+        let new_handler_id = bag.add(Arc::new(|| {}));
+
+        drop(handler_id);
+
+        assert_eq!(bag.len(), 1);
+
+        let merged_handler_id = HandlerId::merge(vec![new_handler_id, bag.add(Arc::new(|| {}))]);
+
+        assert!(merged_handler_id.is_attached());
+
+        merged_handler_id.remove_now();
+
+        assert!(!merged_handler_id.is_attached());
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn is_attached() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        let handler_id_clone = handler_id.clone();
+
+        assert!(handler_id_clone.is_attached());
+
+        handler_id.detach();
+
+        assert!(!handler_id_clone.is_attached());
+    }
+
+    #[test]
+    // Hash only depends on address of the shared state, which never changes
+    #[allow(clippy::mutable_key_type)]
+    fn eq_hash() {
+        use std::collections::HashSet;
+
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        let other_handler_id = bag.add(Arc::new(|| {}));
+
+        assert_eq!(handler_id, handler_id.clone());
+        assert_ne!(handler_id, other_handler_id);
+
+        let mut set = HashSet::new();
+        assert!(set.insert(handler_id.clone()));
+        assert!(!set.insert(handler_id.clone()));
+        assert!(set.insert(other_handler_id));
+        assert!(set.contains(&handler_id));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn leak_reclaim() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let other_bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let key = bag.add(Arc::new(|| {})).leak().unwrap();
+        assert_eq!(bag.len(), 1);
+        assert!(other_bag.reclaim(key).is_none());
+
+        let handler_id = bag.reclaim(key).unwrap();
+        assert_eq!(handler_id.index(), Some(key.index()));
+        drop(handler_id);
+        assert!(bag.is_empty());
+        assert!(bag.reclaim(key).is_none());
+
+        let merged = HandlerId::merge(vec![bag.add(Arc::new(|| {}))]);
+        assert!(merged.leak().is_none());
+        assert_eq!(bag.len(), 1);
+    }
+
+    #[test]
+    fn keep_if() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let subscribe = |succeed: bool| -> Result<(), ()> {
+            let handler_id = bag.add(Arc::new(|| {}));
+            let result = if succeed { Ok(()) } else { Err(()) };
+            handler_id.keep_if(result.is_ok());
+            result
+        };
+
+        assert!(subscribe(false).is_err());
+        assert!(bag.is_empty());
+        assert!(subscribe(true).is_ok());
+        assert_eq!(bag.len(), 1);
+    }
+
+    #[test]
+    fn clones_share_removal() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        let handler_id_clone = handler_id.clone();
+
+        // Dropping one of the clones doesn't remove the handler
+        drop(handler_id);
+        assert_eq!(bag.len(), 1);
+
+        // Handler is removed once the last clone is dropped
+        drop(handler_id_clone);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn tagged() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let mut handler_id = bag.add_with_data(Arc::new(|| {}), "subscriber");
+        assert_eq!(*handler_id.data(), "subscriber");
+        *handler_id.data_mut() = "renamed";
+        assert_eq!(handler_id.index(), Some(0));
+        assert!(bag.owns(&handler_id));
+
+        let (handler_id, data) = handler_id.into_parts();
+        assert_eq!(data, "renamed");
+        assert_eq!(bag.len(), 1);
+
+        drop(handler_id);
+        assert!(bag.is_empty());
+
+        drop(bag.add_with_data(Arc::new(|| {}), ()));
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn weak() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        let weak_handler_id = handler_id.weak();
+        assert!(weak_handler_id.is_alive());
+
+        let upgraded = weak_handler_id.upgrade().unwrap();
+        assert_eq!(upgraded, handler_id);
+        assert!(upgraded.is_attached());
+        drop(upgraded);
+        assert_eq!(bag.len(), 1);
+
+        // Weak reference doesn't keep handler in the bag
+        drop(handler_id);
+        assert!(bag.is_empty());
+        assert!(!weak_handler_id.is_alive());
+        assert!(weak_handler_id.upgrade().is_none());
+    }
+}
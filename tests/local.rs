@@ -0,0 +1,136 @@
+mod local {
+    use event_listener_primitives::{LocalBag, LocalBagOnce};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn regular() {
+        let bag = LocalBag::<Rc<dyn Fn()>>::default();
+        let calls = Rc::new(Cell::new(0));
+
+        {
+            let calls = Rc::clone(&calls);
+            bag.add(Rc::new(move || {
+                calls.set(calls.get() + 1);
+            }))
+            .detach();
+        }
+        {
+            let calls = Rc::clone(&calls);
+            drop(bag.add(Rc::new(move || {
+                calls.set(calls.get() + 1);
+            })))
+        }
+        bag.call(|callback| {
+            callback();
+        });
+
+        assert_eq!(calls.get(), 1);
+
+        bag.call_simple();
+
+        assert_eq!(calls.get(), 2);
+        assert_eq!(bag.len(), 1);
+    }
+
+    #[test]
+    fn once() {
+        let bag = LocalBagOnce::<Box<dyn FnOnce()>>::default();
+        let calls = Rc::new(Cell::new(0));
+
+        {
+            let calls = Rc::clone(&calls);
+            bag.add(Box::new(move || {
+                calls.set(calls.get() + 1);
+            }))
+            .detach();
+        }
+        {
+            let calls = Rc::clone(&calls);
+            drop(bag.add(Box::new(move || {
+                calls.set(calls.get() + 1);
+            })));
+        }
+        bag.call_simple();
+
+        assert_eq!(calls.get(), 1);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn with_arguments() {
+        let bag = LocalBag::<Rc<dyn Fn(&i32, &i32)>, i32, i32>::default();
+        let sum = Rc::new(Cell::new(0));
+
+        let handler_id = {
+            let sum = Rc::clone(&sum);
+            bag.add(Rc::new(move |a1, a2| {
+                sum.set(sum.get() + a1 + a2);
+            }))
+        };
+
+        bag.call_simple(&1, &2);
+
+        assert_eq!(sum.get(), 3);
+
+        assert!(bag.remove(&handler_id));
+
+        bag.call_simple(&1, &2);
+
+        assert_eq!(sum.get(), 3);
+    }
+
+    #[test]
+    fn drop_during_call() {
+        let bag = LocalBag::<Rc<dyn Fn()>>::default();
+        let handler_id = Rc::new(Cell::new(None));
+
+        handler_id.set(Some(bag.add({
+            let handler_id = Rc::clone(&handler_id);
+
+            Rc::new(move || {
+                // Dropping handler ID while handlers are being called must not panic
+                drop(handler_id.take());
+            })
+        })));
+
+        bag.call_simple();
+
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn handler_owning_handler_id() {
+        let bag = LocalBag::<Rc<dyn Fn()>>::default();
+        let handler_id = bag.add(Rc::new(|| {}));
+        let owning_handler_id = bag.add(Rc::new(move || {
+            let _ = &handler_id;
+        }));
+        assert_eq!(bag.len(), 2);
+
+        // Dropping handler drops handler ID it owns, which removes another handler from the bag
+        drop(owning_handler_id);
+        assert!(bag.is_empty());
+
+        let handler_id = bag.add(Rc::new(|| {}));
+        bag.add(Rc::new(move || {
+            let _ = &handler_id;
+        }))
+        .detach();
+        bag.clear();
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn once_handler_owning_handler_id() {
+        let bag = LocalBagOnce::<Box<dyn FnOnce()>>::default();
+        let handler_id = bag.add(Box::new(|| {}));
+        let owning_handler_id = bag.add(Box::new(move || {
+            drop(handler_id);
+        }));
+        assert_eq!(bag.len(), 2);
+
+        drop(owning_handler_id);
+        assert!(bag.is_empty());
+    }
+}
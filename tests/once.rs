@@ -130,4 +130,406 @@ mod once {
             assert_eq!(calls.load(Ordering::SeqCst), 2);
         }
     }
+
+    #[test]
+    fn len() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+
+        assert_eq!(bag.len(), 0);
+        assert!(bag.is_empty());
+
+        let handler_id = bag.add(Box::new(|| {}));
+        bag.add(Box::new(|| {})).detach();
+        bag.add(Box::new(|| {})).detach();
+
+        assert_eq!(bag.len(), 3);
+        assert!(!bag.is_empty());
+
+        drop(handler_id);
+
+        assert_eq!(bag.len(), 2);
+
+        bag.call_simple();
+
+        assert_eq!(bag.len(), 0);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn remove() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+        let other_bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let handler_id = {
+            let calls = Arc::clone(&calls);
+            bag.add(Box::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }))
+        };
+        let other_handler_id = other_bag.add(Box::new(|| {}));
+
+        assert!(!bag.remove(&other_handler_id));
+        assert_eq!(other_bag.len(), 1);
+
+        assert!(bag.remove(&handler_id));
+        assert!(!bag.remove(&handler_id));
+        assert!(bag.is_empty());
+
+        bag.call_simple();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let new_handler_id = bag.add(Box::new(|| {}));
+
+        // Removed handler ID is detached and must not affect other handlers
+        drop(handler_id);
+
+        assert_eq!(bag.len(), 1);
+
+        drop(new_handler_id);
+    }
+
+    #[test]
+    fn call_count() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+
+        assert_eq!(bag.call_simple(), 0);
+
+        bag.add(Box::new(|| {})).detach();
+        bag.add(Box::new(|| {})).detach();
+
+        assert_eq!(bag.call_simple(), 2);
+        assert_eq!(bag.call_simple(), 0);
+    }
+
+    #[test]
+    fn mutable_applicator() {
+        let bag = BagOnce::<Box<dyn FnOnce(&i32) -> i32 + Send + 'static>, i32>::default();
+
+        bag.add(Box::new(|a1| a1 + 1)).detach();
+        bag.add(Box::new(|a1| a1 * 10)).detach();
+
+        let mut results = Vec::new();
+        bag.call(|handler| {
+            results.push(handler(&2));
+        });
+
+        assert_eq!(results, vec![3, 20]);
+    }
+
+    #[test]
+    fn collect() {
+        let bag = BagOnce::<Box<dyn FnOnce(&i32) -> bool + Send + 'static>, i32>::default();
+
+        bag.add(Box::new(|a1| *a1 > 10)).detach();
+        bag.add(Box::new(|a1| *a1 > 0)).detach();
+
+        assert_eq!(bag.call_collect(|handler| handler(&5)), vec![false, true]);
+        assert_eq!(bag.call_collect(|handler| handler(&5)), Vec::<bool>::new());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn capacity() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::with_capacity(10);
+
+        assert!(bag.capacity() >= 10);
+
+        bag.reserve(100);
+
+        assert!(bag.capacity() >= 100);
+
+        for _ in 0..100 {
+            bag.add(Box::new(|| {})).detach();
+        }
+        bag.call_simple();
+
+        assert!(bag.capacity() >= 100);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn shrink_to_fit() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::with_capacity(100);
+        bag.add(Box::new(|| {})).detach();
+
+        bag.shrink_to_fit();
+
+        assert!(bag.capacity() < 100);
+        assert!(bag.capacity() >= 1);
+    }
+
+    #[test]
+    fn owned_arguments() {
+        let bag = BagOnce::<Box<dyn FnOnce(u8, u16) + Send + 'static>, u8, u16>::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..2 {
+            let sum = Arc::clone(&sum);
+            bag.add(Box::new(move |a1, a2| {
+                sum.fetch_add(usize::from(a1) + usize::from(a2), Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_simple_owned(1, 2), 2);
+        assert_eq!(sum.load(Ordering::SeqCst), 6);
+        assert_eq!(bag.call_simple_owned(1, 2), 0);
+    }
+
+    #[test]
+    fn six_arguments() {
+        let bag = BagOnce::<
+            Box<dyn FnOnce(&i32, &i32, &i32, &i32, &i32, &i32) + Send + 'static>,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+        >::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+
+        {
+            let sum = Arc::clone(&sum);
+            bag.add(Box::new(move |a1, a2, a3, a4, a5, a6| {
+                sum.fetch_add((a1 + a2 + a3 + a4 + a5 + a6) as usize, Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_simple(&1, &2, &3, &4, &5, &6), 1);
+        assert_eq!(sum.load(Ordering::SeqCst), 21);
+    }
+
+    #[test]
+    fn add_during_call() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        {
+            let bag = bag.clone();
+            let calls = Arc::clone(&calls);
+            bag.clone()
+                .add(Box::new(move || {
+                    let calls = Arc::clone(&calls);
+                    bag.add(Box::new(move || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                    }))
+                    .detach();
+                }))
+                .detach();
+        }
+
+        assert_eq!(bag.call_simple(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(bag.len(), 1);
+
+        assert_eq!(bag.call_simple(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn debug() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+
+        bag.add(Box::new(|| {})).detach();
+        bag.add(Box::new(|| {})).detach();
+
+        assert_eq!(
+            format!("{:?}", bag),
+            "BagOnce { handler_count: 2, indices: [0, 1] }"
+        );
+        bag.call_simple();
+        assert_eq!(
+            format!("{:?}", bag),
+            "BagOnce { handler_count: 0, indices: [] }"
+        );
+    }
+
+    #[test]
+    fn add_boxed() {
+        type Handler = Box<dyn FnOnce(&usize) + Send + 'static>;
+
+        let bag = BagOnce::<Handler, usize>::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+
+        // Handlers built elsewhere are moved into the bag without boxing them again
+        let handlers = (0..3)
+            .map(|i| {
+                let sum = Arc::clone(&sum);
+                Box::new(move |a1: &usize| {
+                    sum.fetch_add(*a1 + i, Ordering::SeqCst);
+                }) as Handler
+            })
+            .collect::<Vec<_>>();
+        for handler in handlers {
+            bag.add(handler).detach();
+        }
+
+        assert_eq!(bag.call_simple(&1), 3);
+        assert_eq!(sum.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn drain() {
+        let bag = BagOnce::<Box<dyn FnOnce() -> usize + Send + 'static>>::default();
+
+        let handler_id = bag.add(Box::new(|| 0));
+        bag.add(Box::new(|| 1)).detach();
+        bag.add(Box::new(|| 2)).detach();
+
+        let mut handlers = bag.drain();
+        assert_eq!(handlers.len(), 3);
+        assert!(bag.is_empty());
+        drop(handler_id);
+
+        assert_eq!(handlers.next().map(|handler| handler()), Some(0));
+        assert_eq!(
+            handlers.map(|handler| handler()).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(bag.drain().len(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn call_catch_unwind() {
+        let bag = BagOnce::<Box<dyn FnOnce(&usize) + Send + 'static>, usize>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        for i in 0..3 {
+            let calls = Arc::clone(&calls);
+            bag.add(Box::new(move |a1: &usize| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                if i == a1 % 3 {
+                    panic!("Handler {} panicked", i);
+                }
+            }))
+            .detach();
+        }
+
+        let panics = bag.call_catch_unwind(|handler| handler(&1));
+        assert_eq!(panics.len(), 1);
+        assert_eq!(
+            panics[0].downcast_ref::<String>().map(String::as_str),
+            Some("Handler 1 panicked")
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn call_order() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        for name in ["first", "second", "third"] {
+            let order = Arc::clone(&order);
+            bag.add(Box::new(move || {
+                order.lock().unwrap().push(name);
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_simple(), 3);
+        assert_eq!(*order.lock().unwrap(), vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn racing_producers() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..10 {
+            let calls = Arc::clone(&calls);
+            bag.add(Box::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        let counts = (0..4)
+            .map(|_| {
+                let bag = bag.clone();
+                thread::spawn(move || bag.call_simple())
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        // Only one of producers has fired handlers, the rest were no-ops
+        assert_eq!(counts.iter().filter(|&&count| count > 0).count(), 1);
+        assert_eq!(counts.iter().sum::<usize>(), 10);
+        assert_eq!(calls.load(Ordering::SeqCst), 10);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn call_into() {
+        let bag = BagOnce::<Box<dyn FnOnce(&mut Vec<usize>) + Send + 'static>>::default();
+        let mut buffer = Vec::with_capacity(4);
+        let capacity = buffer.capacity();
+        let mut called = Vec::new();
+
+        for round in 0..2 {
+            bag.add_detached(Box::new(move |called| called.push(round * 2)));
+            bag.add_detached(Box::new(move |called| called.push(round * 2 + 1)));
+
+            assert_eq!(bag.call_into(&mut buffer), 2);
+            assert!(bag.is_empty());
+            for handler in buffer.drain(..) {
+                handler(&mut called);
+            }
+            assert_eq!(buffer.capacity(), capacity);
+        }
+        assert_eq!(called, vec![0, 1, 2, 3]);
+
+        assert_eq!(bag.call_into(&mut buffer), 0);
+    }
+
+    #[test]
+    fn call_reporting() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler_ids = (0..3)
+            .map(|_| {
+                let calls = Arc::clone(&calls);
+                bag.add(Box::new(move || {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                }))
+            })
+            .collect::<Vec<_>>();
+        assert!(bag.remove(&handler_ids[1]));
+
+        let indices = bag.call_reporting(|handler| handler());
+        assert_eq!(
+            indices,
+            vec![
+                handler_ids[0].index().unwrap(),
+                handler_ids[2].index().unwrap()
+            ]
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(bag.is_empty());
+
+        assert!(bag.call_reporting(|handler| handler()).is_empty());
+    }
+
+    #[test]
+    fn handler_owning_handler_id() {
+        let bag = BagOnce::<Box<dyn FnOnce() + Send + 'static>>::default();
+        let handler_id = bag.add(Box::new(|| {}));
+        let owning_handler_id = bag.add(Box::new(move || {
+            drop(handler_id);
+        }));
+        assert_eq!(bag.len(), 2);
+
+        // Dropping handler drops handler ID it owns, which removes another handler from the bag
+        drop(owning_handler_id);
+        assert!(bag.is_empty());
+    }
 }
@@ -1,6 +1,9 @@
 mod regular {
-    use event_listener_primitives::Bag;
+    use event_listener_primitives::{
+        Bag, ChangeKind, DispatchBuffer, DispatchMode, DispatchOutcome, WouldBlock,
+    };
     use parking_lot::Mutex;
+    use std::ops::ControlFlow;
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
     use std::thread;
@@ -132,4 +135,2045 @@ mod regular {
             assert_eq!(calls.load(Ordering::SeqCst), 2);
         }
     }
+
+    #[test]
+    fn len() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        assert_eq!(bag.len(), 0);
+        assert!(bag.is_empty());
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        bag.add(Arc::new(|| {})).detach();
+
+        assert_eq!(bag.len(), 2);
+        assert!(!bag.is_empty());
+
+        drop(handler_id);
+
+        assert_eq!(bag.len(), 1);
+
+        bag.call_simple();
+
+        assert_eq!(bag.len(), 1);
+    }
+
+    #[test]
+    fn insertion_order() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+
+        let mut handler_ids = Vec::new();
+        for i in 0..10 {
+            let calls = Arc::clone(&calls);
+            handler_ids.push(bag.add(Arc::new(move || {
+                calls.lock().push(i);
+            })));
+        }
+        // Remove some handlers from the middle to shuffle internal storage a bit
+        drop(handler_ids.remove(3));
+        drop(handler_ids.remove(6));
+
+        bag.call_simple();
+
+        assert_eq!(*calls.lock(), vec![0, 1, 2, 4, 5, 6, 8, 9]);
+    }
+
+    #[test]
+    fn clear() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let handler_id = {
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }))
+        };
+        bag.add(Arc::new(|| {})).detach();
+
+        bag.clear();
+
+        assert!(bag.is_empty());
+
+        bag.call_simple();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let new_handler_id = bag.add(Arc::new(|| {}));
+
+        // Stale handler ID must not affect handlers added after clearing
+        drop(handler_id);
+
+        assert_eq!(bag.len(), 1);
+
+        drop(new_handler_id);
+    }
+
+    #[test]
+    fn remove() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let other_bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let handler_id = {
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }))
+        };
+        let other_handler_id = other_bag.add(Arc::new(|| {}));
+
+        assert!(!bag.remove(&other_handler_id));
+        assert_eq!(other_bag.len(), 1);
+
+        assert!(bag.remove(&handler_id));
+        assert!(!bag.remove(&handler_id));
+        assert!(bag.is_empty());
+
+        bag.call_simple();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let new_handler_id = bag.add(Arc::new(|| {}));
+
+        // Removed handler ID is detached and must not affect other handlers
+        drop(handler_id);
+
+        assert_eq!(bag.len(), 1);
+
+        drop(new_handler_id);
+    }
+
+    #[test]
+    fn call_count() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        assert_eq!(bag.call_simple(), 0);
+
+        bag.add(Arc::new(|| {})).detach();
+        bag.add(Arc::new(|| {})).detach();
+
+        assert_eq!(bag.call_simple(), 2);
+        assert_eq!(bag.call(|handler| handler()), 2);
+        assert_eq!(bag.call_simple(), 2);
+    }
+
+    #[test]
+    fn mutable_applicator() {
+        let bag = Bag::<Arc<dyn Fn(&i32) -> i32 + Send + Sync + 'static>, i32>::default();
+
+        bag.add(Arc::new(|a1| a1 + 1)).detach();
+        bag.add(Arc::new(|a1| a1 * 10)).detach();
+
+        let mut results = Vec::new();
+        bag.call(|handler| {
+            results.push(handler(&2));
+        });
+
+        assert_eq!(results, vec![3, 20]);
+    }
+
+    #[test]
+    fn collect() {
+        let bag = Bag::<Arc<dyn Fn(&i32) -> bool + Send + Sync + 'static>, i32>::default();
+
+        bag.add(Arc::new(|a1| *a1 > 10)).detach();
+        bag.add(Arc::new(|a1| *a1 > 0)).detach();
+
+        assert_eq!(bag.call_collect(|handler| handler(&5)), vec![false, true]);
+        assert_eq!(bag.call_collect(|handler| handler(&5)), vec![false, true]);
+    }
+
+    #[test]
+    fn until() {
+        let bag = Bag::<Arc<dyn Fn(&i32) -> Option<i32> + Send + Sync + 'static>, i32>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        for multiplier in [0, 2, 3] {
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move |a1| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Some(a1 * multiplier).filter(|result| *result > 0)
+            }))
+            .detach();
+        }
+
+        let result = bag.call_until(|handler| match handler(&5) {
+            Some(result) => ControlFlow::Break(result),
+            None => ControlFlow::Continue(()),
+        });
+
+        assert_eq!(result, Some(10));
+        // Third handler must not be called
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let result = bag.call_until(|handler| match handler(&0) {
+            Some(result) => ControlFlow::Break(result),
+            None => ControlFlow::Continue(()),
+        });
+
+        assert_eq!(result, None);
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn capacity() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::with_capacity(10);
+
+        assert!(bag.capacity() >= 10);
+
+        bag.reserve(100);
+
+        assert!(bag.capacity() >= 100);
+
+        for _ in 0..100 {
+            bag.add(Arc::new(|| {})).detach();
+        }
+        bag.call_simple();
+
+        assert!(bag.capacity() >= 100);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn shrink_to_fit() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::with_capacity(100);
+        bag.add(Arc::new(|| {})).detach();
+
+        bag.shrink_to_fit();
+
+        assert!(bag.capacity() < 100);
+        assert!(bag.capacity() >= 1);
+    }
+
+    #[test]
+    fn retain() {
+        let bag = Bag::<Arc<dyn Fn() -> usize + Send + Sync + 'static>>::default();
+
+        let handler_ids = (0..4)
+            .map(|i| bag.add(Arc::new(move || i)))
+            .collect::<Vec<_>>();
+
+        bag.retain(|handler| handler() % 2 == 0);
+
+        assert_eq!(bag.call_collect(|handler| handler()), vec![0, 2]);
+
+        // Handler IDs of pruned handlers are harmless
+        drop(handler_ids);
+
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn owned_arguments() {
+        let bag = Bag::<Arc<dyn Fn(u8, u16) + Send + Sync + 'static>, u8, u16>::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..2 {
+            let sum = Arc::clone(&sum);
+            bag.add(Arc::new(move |a1, a2| {
+                sum.fetch_add(usize::from(a1) + usize::from(a2), Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_simple_owned(1, 2), 2);
+        assert_eq!(sum.load(Ordering::SeqCst), 6);
+        assert_eq!(bag.call_simple_owned(1, 2), 2);
+    }
+
+    #[test]
+    fn mutable_argument() {
+        let bag =
+            Bag::<Arc<dyn Fn(&mut Vec<usize>) + Send + Sync + 'static>, Vec<usize>>::default();
+
+        for i in 0..3 {
+            bag.add(Arc::new(move |items| {
+                items.push(i);
+            }))
+            .detach();
+        }
+
+        let mut items = Vec::new();
+
+        assert_eq!(bag.call_simple_mut(&mut items), 3);
+        assert_eq!(items, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn six_arguments() {
+        let bag = Bag::<
+            Arc<dyn Fn(&i32, &i32, &i32, &i32, &i32, &i32) + Send + Sync + 'static>,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+            i32,
+        >::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+
+        {
+            let sum = Arc::clone(&sum);
+            bag.add(Arc::new(move |a1, a2, a3, a4, a5, a6| {
+                sum.fetch_add((a1 + a2 + a3 + a4 + a5 + a6) as usize, Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_simple(&1, &2, &3, &4, &5, &6), 1);
+        assert_eq!(sum.load(Ordering::SeqCst), 21);
+    }
+
+    #[test]
+    fn all_arities() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler = {
+            let calls = Arc::clone(&calls);
+            move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+
+        {
+            let bag = Bag::default();
+            let handler = handler.clone();
+            bag.add(Arc::new(move |_a1: &i32, _a2: &i32| handler()))
+                .detach();
+            bag.call_simple(&1, &2);
+        }
+        {
+            let bag = Bag::default();
+            let handler = handler.clone();
+            bag.add(Arc::new(move |_a1: &i32, _a2: &i32, _a3: &i32| handler()))
+                .detach();
+            bag.call_simple(&1, &2, &3);
+        }
+        {
+            let bag = Bag::default();
+            let handler = handler.clone();
+            bag.add(Arc::new(
+                move |_a1: &i32, _a2: &i32, _a3: &i32, _a4: &i32| handler(),
+            ))
+            .detach();
+            bag.call_simple(&1, &2, &3, &4);
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        let bag = Bag::<Arc<dyn Fn(&usize) + Send + Sync + 'static>, usize>::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..10 {
+            let sum = Arc::clone(&sum);
+            bag.add(Arc::new(move |a1| {
+                sum.fetch_add(*a1, Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_parallel(|handler| handler(&2)), 10);
+        assert_eq!(sum.load(Ordering::SeqCst), 20);
+    }
+
+    #[test]
+    fn snapshot() {
+        let bag = Bag::<Arc<dyn Fn() -> usize + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        for i in 0..3 {
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                i
+            }))
+            .detach();
+        }
+
+        let handlers = bag.snapshot();
+        assert_eq!(handlers.len(), 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            handlers.iter().map(|handler| handler()).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn add_during_call() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        {
+            let bag = bag.clone();
+            let calls = Arc::clone(&calls);
+            bag.clone()
+                .add(Arc::new(move || {
+                    let calls = Arc::clone(&calls);
+                    bag.add(Arc::new(move || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                    }))
+                    .detach();
+                }))
+                .detach();
+        }
+
+        assert_eq!(bag.call_simple(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(bag.len(), 2);
+
+        assert_eq!(bag.call_simple(), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(bag.len(), 3);
+
+        // Break reference cycle between the bag and the first handler
+        bag.clear();
+    }
+
+    #[test]
+    fn call_during_call() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        {
+            let bag = bag.clone();
+            let calls = Arc::clone(&calls);
+            bag.clone()
+                .add(Arc::new(move || {
+                    // Only the outermost call dispatches nested one
+                    if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        assert_eq!(bag.call_simple(), 1);
+                    }
+                }))
+                .detach();
+        }
+
+        assert_eq!(bag.call_simple(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Break reference cycle between the bag and the handler
+        bag.clear();
+    }
+
+    #[test]
+    fn clear_during_call() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let len_seen = Arc::new(AtomicUsize::new(usize::MAX));
+
+        {
+            let bag = bag.clone();
+            let len_seen = Arc::clone(&len_seen);
+            bag.clone()
+                .add(Arc::new(move || {
+                    bag.clear();
+                    len_seen.store(bag.len(), Ordering::SeqCst);
+                }))
+                .detach();
+        }
+        {
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_simple(), 2);
+        assert_eq!(len_seen.load(Ordering::SeqCst), 0);
+        // Second handler was collected before it was cleared, so it is still called
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(bag.is_empty());
+        assert_eq!(bag.call_simple(), 0);
+    }
+
+    #[test]
+    fn priority() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        for (name, priority) in [("a", 0), ("b", 10), ("c", -5), ("d", 10), ("e", 0)] {
+            let order = Arc::clone(&order);
+            bag.add_with_priority(
+                Arc::new(move || {
+                    order.lock().push(name);
+                }),
+                priority,
+            )
+            .detach();
+        }
+        {
+            let order = Arc::clone(&order);
+            bag.add(Arc::new(move || {
+                order.lock().push("f");
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_simple(), 6);
+        assert_eq!(*order.lock(), vec!["b", "d", "a", "e", "f", "c"]);
+    }
+
+    #[test]
+    fn call_once() {
+        let bag = Bag::<Arc<dyn Fn(&usize) + Send + Sync + 'static>, usize>::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+
+        let handler_id = {
+            let sum = Arc::clone(&sum);
+            bag.add(Arc::new(move |a1| {
+                sum.fetch_add(*a1, Ordering::SeqCst);
+            }))
+        };
+        {
+            let sum = Arc::clone(&sum);
+            bag.add(Arc::new(move |a1| {
+                sum.fetch_add(*a1 * 10, Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_once_simple(&1), 2);
+        assert_eq!(sum.load(Ordering::SeqCst), 11);
+        assert!(bag.is_empty());
+        assert!(!bag.remove(&handler_id));
+
+        assert_eq!(bag.call_once_simple(&1), 0);
+        assert_eq!(sum.load(Ordering::SeqCst), 11);
+    }
+
+    #[test]
+    fn try_call() {
+        let bag = Bag::<Arc<dyn Fn() -> Result<(), usize> + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        for i in 0..4 {
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                if i % 2 == 1 {
+                    Err(i)
+                } else {
+                    Ok(())
+                }
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.try_call(|handler| handler()), vec![1, 3]);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        assert_eq!(bag.try_call_stop(|handler| handler()), Err(1));
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+
+        bag.clear();
+        assert_eq!(bag.try_call_stop(|handler| handler()), Ok(()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn forward_to() {
+        use std::sync::mpsc;
+
+        let bag = Bag::<Arc<dyn Fn(&usize) + Send + Sync + 'static>, usize>::default();
+        let (sender, receiver) = mpsc::channel();
+
+        let handler_id = bag.forward_to(sender);
+        bag.call_simple(&1);
+        bag.call_simple(&2);
+        drop(handler_id);
+        bag.call_simple(&3);
+
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+        // Sender was dropped together with the handler
+        assert!(receiver.recv().is_err());
+    }
+
+    #[cfg(all(feature = "futures", feature = "std"))]
+    #[test]
+    fn subscribe_stream() {
+        use futures::executor::block_on;
+        use futures::StreamExt;
+
+        let bag = Bag::<Arc<dyn Fn(&usize) + Send + Sync + 'static>, usize>::default();
+
+        let (stream, handler_id) = bag.subscribe_stream();
+        bag.call_simple(&1);
+        bag.call_simple(&2);
+        drop(handler_id);
+        bag.call_simple(&3);
+        assert_eq!(block_on(stream.collect::<Vec<_>>()), vec![1, 2]);
+
+        let (stream, handler_id) = bag.subscribe_stream();
+        handler_id.detach();
+        assert_eq!(bag.len(), 1);
+        drop(stream);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    #[cfg(all(feature = "futures", feature = "std"))]
+    fn subscribe_stream_bounded() {
+        use event_listener_primitives::Overflow;
+        use futures::executor::block_on;
+        use futures::{Stream, StreamExt};
+
+        let bag = Bag::<Arc<dyn Fn(&usize) + Send + Sync + 'static>, usize>::default();
+
+        let (oldest, oldest_handler_id) = bag.subscribe_stream_bounded(2, Overflow::DropOldest);
+        let (newest, newest_handler_id) = bag.subscribe_stream_bounded(2, Overflow::DropNewest);
+        for event in 1..=3 {
+            bag.call_simple(&event);
+        }
+        assert_eq!(oldest.size_hint(), (2, None));
+        drop(oldest_handler_id);
+        drop(newest_handler_id);
+        assert_eq!(block_on(oldest.collect::<Vec<_>>()), vec![2, 3]);
+        assert_eq!(block_on(newest.collect::<Vec<_>>()), vec![1, 2]);
+
+        // Blocked thread makes progress as the stream is consumed
+        let (mut stream, handler_id) = bag.subscribe_stream_bounded(1, Overflow::Block);
+        let producer = {
+            let bag = bag.clone();
+            thread::spawn(move || {
+                for event in 1..=3 {
+                    bag.call_simple(&event);
+                }
+                drop(handler_id);
+            })
+        };
+        assert_eq!(block_on(stream.by_ref().collect::<Vec<_>>()), vec![1, 2, 3]);
+        producer.join().unwrap();
+        assert!(bag.is_empty());
+
+        // Dropping the stream unblocks the thread if it is blocked and removes the handler
+        let (stream, handler_id) = bag.subscribe_stream_bounded(1, Overflow::Block);
+        handler_id.detach();
+        bag.call_simple(&1);
+        let producer = {
+            let bag = bag.clone();
+            thread::spawn(move || bag.call_simple(&2))
+        };
+        drop(stream);
+        producer.join().unwrap();
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn add_keyed() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let other_bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let (handler_id, key) = bag.add_keyed(Arc::new(|| {}));
+        let (_other_handler_id, other_key) = other_bag.add_keyed(Arc::new(|| {}));
+        assert_eq!(handler_id.index(), Some(key.index()));
+        assert_eq!(key.index(), other_key.index());
+        assert_ne!(key, other_key);
+
+        assert!(!bag.remove_key(other_key));
+        assert_eq!(bag.len(), 1);
+        assert!(bag.remove_key(key));
+        assert!(bag.is_empty());
+        assert!(!bag.remove_key(key));
+        drop(handler_id);
+        assert_eq!(other_bag.len(), 1);
+    }
+
+    #[test]
+    fn weak_bag() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        {
+            let weak_bag = bag.downgrade();
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move || {
+                if let Some(bag) = weak_bag.upgrade() {
+                    calls.fetch_add(bag.len(), Ordering::SeqCst);
+                }
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_simple(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let weak_bag = bag.downgrade();
+        drop(bag);
+        // Handler didn't keep the bag alive
+        assert!(weak_bag.upgrade().is_none());
+    }
+
+    #[test]
+    fn limit() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::with_limit(2);
+
+        let handler_id = bag.try_add(Arc::new(|| {})).unwrap();
+        bag.try_add(Arc::new(|| {})).unwrap().detach();
+        let error = bag.try_add(Arc::new(|| {})).unwrap_err();
+        assert_eq!(error.current, 2);
+        assert_eq!(error.max, 2);
+        assert_eq!(bag.len(), 2);
+
+        drop(handler_id);
+        bag.try_add(Arc::new(|| {})).unwrap().detach();
+
+        // Plain `add()` ignores the limit
+        bag.add(Arc::new(|| {})).detach();
+        assert_eq!(bag.len(), 3);
+        assert!(bag.try_add(Arc::new(|| {})).is_err());
+
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        for _ in 0..10 {
+            bag.try_add(Arc::new(|| {})).unwrap().detach();
+        }
+    }
+
+    #[test]
+    fn call_with_buffer() {
+        let bag = Bag::<Arc<dyn Fn(&usize) + Send + Sync + 'static>, usize>::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..10 {
+            let sum = Arc::clone(&sum);
+            bag.add(Arc::new(move |a1| {
+                sum.fetch_add(*a1, Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        let mut buffer = DispatchBuffer::with_capacity(10);
+        let handler = bag.snapshot().remove(0);
+        assert_eq!(bag.call_with_buffer(&mut buffer, |handler| handler(&1)), 10);
+        assert_eq!(bag.call_with_buffer(&mut buffer, |handler| handler(&2)), 10);
+        assert_eq!(sum.load(Ordering::SeqCst), 30);
+        assert!(buffer.capacity() >= 10);
+
+        // Buffer doesn't keep handlers alive between calls
+        bag.clear();
+        assert_eq!(Arc::strong_count(&handler), 1);
+        assert_eq!(bag.call_with_buffer(&mut buffer, |handler| handler(&3)), 0);
+    }
+
+    #[test]
+    fn call_locked() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        for (name, priority) in [("a", 0), ("b", 1), ("c", 0)] {
+            let order = Arc::clone(&order);
+            bag.add_with_priority(
+                Arc::new(move || {
+                    order.lock().push(name);
+                }),
+                priority,
+            )
+            .detach();
+        }
+
+        assert_eq!(
+            bag.call_locked(|handler| {
+                // Handler is not cloned
+                assert_eq!(Arc::strong_count(handler), 1);
+                handler();
+            }),
+            3
+        );
+        assert_eq!(*order.lock(), vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn call_nonblocking() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        {
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        assert_eq!(bag.call_nonblocking(|handler| handler()), Ok(1));
+        // Bag is locked for writing while predicate is called
+        bag.retain(|_handler| {
+            assert_eq!(bag.call_nonblocking(|handler| handler()), Err(WouldBlock));
+            true
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "parking_lot")]
+    #[test]
+    fn call_timeout() {
+        use event_listener_primitives::Timeout;
+        use std::time::Duration;
+
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        {
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }))
+            .detach();
+        }
+
+        assert_eq!(
+            bag.call_timeout(|handler| handler(), Duration::from_millis(10)),
+            Ok(1)
+        );
+        bag.retain(|_handler| {
+            assert_eq!(
+                bag.call_timeout(|handler| handler(), Duration::from_millis(10)),
+                Err(Timeout)
+            );
+            true
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn debug() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        bag.add(Arc::new(|| {})).detach();
+        drop(bag.add(Arc::new(|| {})));
+        bag.add(Arc::new(|| {})).detach();
+
+        assert_eq!(
+            format!("{:?}", bag),
+            "Bag { handler_count: 3, indices: [0, 1, 3] }"
+        );
+        assert_eq!(
+            format!("{:?}", handler_id),
+            "HandlerId { index: Some(0), attached: true }"
+        );
+        bag.retain(|_handler| {
+            assert_eq!(format!("{:?}", bag), "Bag { handlers: <locked> }");
+            true
+        });
+        handler_id.remove_now();
+        assert_eq!(
+            format!("{:?}", handler_id),
+            "HandlerId { index: Some(0), attached: false }"
+        );
+    }
+
+    #[test]
+    fn add_detached() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        {
+            let calls = Arc::clone(&calls);
+            bag.add_detached(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }));
+        }
+
+        assert_eq!(bag.call_simple(), 1);
+        assert_eq!(bag.call_simple(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn add_all() {
+        let bag = Bag::<Arc<dyn Fn() -> usize + Send + Sync + 'static>>::default();
+
+        let handler_ids = bag.add_all(
+            (0..3_usize)
+                .map(|i| Arc::new(move || i) as Arc<dyn Fn() -> usize + Send + Sync + 'static>),
+        );
+
+        assert_eq!(handler_ids.len(), 3);
+        assert_eq!(
+            handler_ids
+                .iter()
+                .map(|handler_id| handler_id.index())
+                .collect::<Vec<_>>(),
+            vec![Some(0), Some(1), Some(2)]
+        );
+        assert_eq!(bag.call_collect(|handler| handler()), vec![0, 1, 2]);
+
+        drop(handler_ids);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn on_change() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+
+        {
+            let changes = Arc::clone(&changes);
+            bag.on_change(move |kind, index| {
+                changes.lock().push((kind, index));
+            });
+        }
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        let (other_handler_id, key) = bag.add_keyed(Arc::new(|| {}));
+        drop(handler_id);
+        assert!(bag.remove_key(key));
+        drop(other_handler_id);
+
+        assert_eq!(
+            *changes.lock(),
+            vec![
+                (ChangeKind::Added, 0),
+                (ChangeKind::Added, 1),
+                (ChangeKind::Removed, 0),
+                (ChangeKind::Removed, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn on_change_bulk_removals() {
+        let bag = Bag::<Arc<dyn Fn() -> bool + Send + Sync + 'static>>::default();
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        {
+            let removed = Arc::clone(&removed);
+            bag.on_change(move |kind, index| {
+                if kind == ChangeKind::Removed {
+                    removed.lock().push(index);
+                }
+            });
+        }
+        let add_handlers = || {
+            bag.add_detached(Arc::new(|| true));
+            bag.add_detached(Arc::new(|| false));
+        };
+        let take_removed = || removed.lock().drain(..).collect::<Vec<_>>();
+
+        add_handlers();
+        bag.clear();
+        assert_eq!(take_removed(), vec![1, 0]);
+
+        add_handlers();
+        bag.retain(|handler| handler());
+        assert_eq!(take_removed(), vec![3]);
+        bag.clear();
+        take_removed();
+
+        add_handlers();
+        bag.call_retaining(|handler| handler());
+        assert_eq!(take_removed(), vec![5]);
+        bag.clear();
+        take_removed();
+
+        add_handlers();
+        assert_eq!(bag.drain().len(), 2);
+        assert_eq!(take_removed(), vec![6, 7]);
+
+        // Removals are reported before handlers are called
+        add_handlers();
+        let removed_before_call = Mutex::new(Vec::new());
+        bag.call_once(|_handler| removed_before_call.lock().push(removed.lock().len()));
+        assert_eq!(*removed_before_call.lock(), vec![2, 2]);
+        assert_eq!(take_removed(), vec![8, 9]);
+    }
+
+    #[test]
+    fn add_unique() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let handler: Arc<dyn Fn() + Send + Sync + 'static> = Arc::new(|| {});
+
+        let handler_id = bag.add_unique(Arc::clone(&handler));
+        assert!(handler_id.is_some());
+        assert!(bag.add_unique(Arc::clone(&handler)).is_none());
+        assert_eq!(bag.len(), 1);
+
+        bag.add_unique(Arc::new(|| {})).unwrap().detach();
+        assert_eq!(bag.len(), 2);
+
+        drop(handler_id);
+        assert!(bag.add_unique(handler).is_some());
+    }
+
+    #[test]
+    fn replace() {
+        let bag = Bag::<Arc<dyn Fn() -> &'static str + Send + Sync + 'static>>::default();
+
+        bag.add(Arc::new(|| "first")).detach();
+        let handler_id = bag.add_with_priority(Arc::new(|| "old"), 1);
+        let handler_id = bag.replace(&handler_id, Arc::new(|| "new"));
+        assert_eq!(bag.len(), 2);
+        // Priority of the old handler is retained
+        assert_eq!(bag.call_collect(|handler| handler()), vec!["new", "first"]);
+
+        let other_bag = Bag::<Arc<dyn Fn() -> &'static str + Send + Sync + 'static>>::default();
+        let other_handler_id = other_bag.add(Arc::new(|| "other"));
+        bag.replace(&other_handler_id, Arc::new(|| "last")).detach();
+        assert_eq!(other_bag.len(), 1);
+        // Handler ID of a different bag is not detached
+        drop(other_handler_id);
+        assert!(other_bag.is_empty());
+        assert_eq!(
+            bag.call_collect(|handler| handler()),
+            vec!["new", "first", "last"]
+        );
+
+        drop(handler_id);
+        assert_eq!(bag.call_collect(|handler| handler()), vec!["first", "last"]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn call_catch_unwind() {
+        let bag = Bag::<Arc<dyn Fn(&usize) + Send + Sync + 'static>, usize>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        for i in 0..3 {
+            let calls = Arc::clone(&calls);
+            bag.add(Arc::new(move |a1: &usize| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                if i == a1 % 3 {
+                    panic!("Handler {} panicked", i);
+                }
+            }))
+            .detach();
+        }
+
+        let panics = bag.call_catch_unwind(|handler| handler(&1));
+        assert_eq!(panics.len(), 1);
+        assert_eq!(
+            panics[0].downcast_ref::<String>().map(String::as_str),
+            Some("Handler 1 panicked")
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        assert_eq!(bag.call_catch_unwind(|handler| handler(&0)).len(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[test]
+    fn from_iter() {
+        let bag = (0..3_usize)
+            .map(|i| Arc::new(move || i) as Arc<dyn Fn() -> usize + Send + Sync + 'static>)
+            .collect::<Bag<_>>();
+
+        assert_eq!(bag.len(), 3);
+        assert_eq!(bag.call_collect(|handler| handler()), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn concurrent_calls() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        {
+            let bag = bag.clone();
+            let calls = Arc::clone(&calls);
+            bag.clone()
+                .add(Arc::new(move || {
+                    // Bag is only locked for reading while handlers are called with
+                    // `call_locked()`, so other calls are not blocked
+                    assert_eq!(bag.call_nonblocking(|_handler| {}), Ok(1));
+                    calls.fetch_add(1, Ordering::SeqCst);
+                }))
+                .detach();
+        }
+
+        assert_eq!(bag.call_locked(|handler| handler()), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        bag.clear();
+    }
+
+    #[test]
+    fn dispatch() {
+        use event_listener_primitives::dispatch;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let bag_a = Bag::<Arc<dyn Fn(&usize) + Send + Sync + 'static>, usize>::default();
+        let bag_b =
+            Bag::<Arc<dyn Fn(&usize, &usize) + Send + Sync + 'static>, usize, usize>::default();
+        let bag_c = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        for _ in 0..2 {
+            let order = Arc::clone(&order);
+            bag_a.add_detached(Arc::new(move |x| {
+                order.lock().push(*x);
+            }));
+        }
+        {
+            let order = Arc::clone(&order);
+            bag_b.add_detached(Arc::new(move |x, y| {
+                order.lock().push(x + y);
+            }));
+        }
+        {
+            let order = Arc::clone(&order);
+            bag_c.add_detached(Arc::new(move || {
+                order.lock().push(0);
+            }));
+        }
+
+        let x = 1;
+        assert_eq!(dispatch![bag_c(), bag_a(&x), bag_b(&x, &2)], 4);
+        assert_eq!(*order.lock(), vec![0, 1, 1, 3]);
+        assert_eq!(dispatch![], 0);
+    }
+
+    #[test]
+    fn owned_arc_argument() {
+        let bag = Bag::<Arc<dyn Fn(Arc<Vec<u8>>) + Send + Sync + 'static>, Arc<Vec<u8>>>::default();
+        let kept = Arc::new(Mutex::new(Vec::new()));
+
+        for _ in 0..3 {
+            let kept = Arc::clone(&kept);
+            bag.add_detached(Arc::new(move |data| {
+                kept.lock().push(data);
+            }));
+        }
+
+        let data = Arc::new(vec![0_u8; 1024]);
+        assert_eq!(bag.call_simple_owned(Arc::clone(&data)), 3);
+        assert_eq!(Arc::strong_count(&data), 4);
+        assert!(kept
+            .lock()
+            .iter()
+            .all(|kept_data| Arc::ptr_eq(kept_data, &data)));
+    }
+
+    #[test]
+    fn call_enumerated() {
+        let bag = Bag::<Arc<dyn Fn() -> &'static str + Send + Sync + 'static>>::default();
+
+        bag.add_detached(Arc::new(|| "a"));
+        bag.add_with_priority(Arc::new(|| "b"), 1).detach();
+        bag.add_detached(Arc::new(|| "c"));
+
+        let mut calls = Vec::new();
+        assert_eq!(
+            bag.call_enumerated(|position, handler| calls.push((position, handler()))),
+            3
+        );
+        assert_eq!(calls, vec![(0, "b"), (1, "a"), (2, "c")]);
+    }
+
+    #[test]
+    fn strong_count() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        assert!(bag.is_unique());
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        let weak_bag = bag.downgrade();
+        assert!(bag.is_unique());
+
+        let bag_clone = bag.clone();
+        assert_eq!(bag.strong_count(), 2);
+        assert!(!bag_clone.is_unique());
+
+        drop(bag_clone);
+        assert!(bag.is_unique());
+        drop((handler_id, weak_bag));
+    }
+
+    #[test]
+    fn drop_handler_id_during_call() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler_id = Arc::new(Mutex::new(None));
+
+        {
+            let handler_id = Arc::clone(&handler_id);
+            bag.add_detached(Arc::new(move || {
+                // Handler ID of the second handler is dropped concurrently during the call
+                let handler_id = handler_id.lock().take();
+                thread::spawn(move || drop(handler_id)).join().unwrap();
+            }));
+        }
+        {
+            let calls = Arc::clone(&calls);
+            handler_id.lock().replace(bag.add(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            })));
+        }
+
+        // Set of handlers collected at the beginning of the call is authoritative
+        assert_eq!(bag.call_simple(), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(bag.len(), 1);
+
+        assert_eq!(bag.call_simple(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn index_snapshot() {
+        use event_listener_primitives::IndexSnapshot;
+
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        bag.add_detached(Arc::new(|| {}));
+        let handler_id = bag.add(Arc::new(|| {}));
+        bag.add_detached(Arc::new(|| {}));
+        drop(handler_id);
+
+        let snapshot = bag.index_snapshot();
+        assert_eq!(
+            snapshot,
+            IndexSnapshot {
+                indices: vec![0, 2],
+                next_index: 3,
+            }
+        );
+
+        let serialized = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serialized, r#"{"indices":[0,2],"next_index":3}"#);
+        assert_eq!(
+            serde_json::from_str::<IndexSnapshot>(&serialized).unwrap(),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn applicator_receives_handler_by_reference() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        fn handler() {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        // Handlers are stored as is, so applicator gets `&F` without any boxing in between
+        let bag = Bag::<fn()>::default();
+        bag.add_detached(handler);
+        bag.add_detached(handler);
+
+        assert_eq!(bag.call(|handler: &fn()| handler()), 2);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn call_rotating() {
+        let bag = Bag::<usize>::default();
+        for handler in 0..3_usize {
+            bag.add_detached(handler);
+        }
+
+        let mut order = Vec::new();
+        for _ in 0..4 {
+            let mut called = Vec::new();
+            assert_eq!(bag.call_rotating(|handler| called.push(*handler)), 3);
+            order.push(called);
+        }
+        assert_eq!(
+            order,
+            vec![vec![0, 1, 2], vec![1, 2, 0], vec![2, 0, 1], vec![0, 1, 2]]
+        );
+
+        // Regular calls don't affect the cursor
+        bag.call(|_| {});
+        let mut called = Vec::new();
+        bag.call_rotating(|handler| called.push(*handler));
+        assert_eq!(called, vec![1, 2, 0]);
+
+        assert_eq!(Bag::<usize>::default().call_rotating(|_| {}), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "debug-checks")]
+    fn on_change_during_call() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let reported = Arc::new(AtomicUsize::new(0));
+        {
+            let reported = Arc::clone(&reported);
+            bag.on_change_during_call(move || {
+                reported.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        bag.add_detached(Arc::new(|| {}));
+
+        bag.call_simple();
+        assert_eq!(reported.load(Ordering::SeqCst), 0);
+
+        let handler_id = Arc::new(Mutex::new(None));
+        {
+            let handler_id = Arc::clone(&handler_id);
+            bag.add_detached(Arc::new(move || {
+                handler_id.lock().take();
+            }));
+        }
+        handler_id.lock().replace(bag.add(Arc::new(|| {})));
+
+        // Handler ID is dropped while handlers are being called
+        assert_eq!(bag.call_simple(), 3);
+        assert_eq!(reported.load(Ordering::SeqCst), 1);
+
+        bag.call_simple();
+        assert_eq!(reported.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn call_retaining() {
+        // Handler returns `true` while it wants to stay in the bag
+        let bag = Bag::<Arc<dyn Fn() -> bool + Send + Sync + 'static>>::default();
+        let calls = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0)]);
+        for handler in 0..2 {
+            let calls = Arc::clone(&calls);
+            bag.add_detached(Arc::new(move || {
+                // First handler fires once, second one twice
+                calls[handler].fetch_add(1, Ordering::SeqCst) < handler
+            }));
+        }
+        let removed = Arc::new(AtomicUsize::new(0));
+        {
+            let removed = Arc::clone(&removed);
+            bag.on_change(move |kind, _index| {
+                if kind == ChangeKind::Removed {
+                    removed.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+
+        assert_eq!(bag.call_retaining(|handler| handler()), 2);
+        assert_eq!(bag.len(), 1);
+
+        assert_eq!(bag.call_retaining(|handler| handler()), 1);
+        assert!(bag.is_empty());
+        assert_eq!(bag.call_retaining(|handler| handler()), 0);
+
+        assert_eq!(calls[0].load(Ordering::SeqCst), 1);
+        assert_eq!(calls[1].load(Ordering::SeqCst), 2);
+        assert_eq!(removed.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn call_retaining_weak_upstream() {
+        use std::sync::Weak;
+
+        let bag = Bag::<Arc<dyn Fn() -> bool + Send + Sync + 'static>>::default();
+        let observed = Arc::new(AtomicUsize::new(0));
+        {
+            let observed = Arc::downgrade(&observed);
+            // Handler stays in the bag only while observed object is alive
+            bag.add_detached(Arc::new(move || match Weak::upgrade(&observed) {
+                Some(observed) => {
+                    observed.fetch_add(1, Ordering::SeqCst);
+                    true
+                }
+                None => false,
+            }));
+        }
+
+        assert_eq!(bag.call_retaining(|handler| handler()), 1);
+        assert_eq!(observed.load(Ordering::SeqCst), 1);
+        assert_eq!(bag.len(), 1);
+
+        drop(observed);
+        assert_eq!(bag.call_retaining(|handler| handler()), 1);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn call_first() {
+        let bag = Bag::<Arc<dyn Fn(&usize) -> usize + Send + Sync + 'static>, usize>::default();
+        assert_eq!(bag.call_first(|handler| handler(&1)), None);
+
+        bag.add_detached(Arc::new(|value| value + 1));
+        bag.add_detached(Arc::new(|value| value + 2));
+        let request = String::from("moved into applicator");
+        assert_eq!(
+            bag.call_first(move |handler| {
+                drop(request);
+                handler(&1)
+            }),
+            Some(2)
+        );
+
+        let _handler_id = bag.add_with_priority(Arc::new(|value| value + 3), 1);
+        assert_eq!(bag.call_first(|handler| handler(&1)), Some(4));
+        assert_eq!(bag.len(), 3);
+    }
+
+    #[test]
+    fn new_named() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::new_named("on_close");
+        assert_eq!(bag.name(), Some("on_close"));
+        assert_eq!(bag.clone().name(), Some("on_close"));
+        assert_eq!(
+            format!("{:?}", bag),
+            r#"Bag { name: "on_close", handler_count: 0, indices: [] }"#
+        );
+
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        assert_eq!(bag.name(), None);
+        assert_eq!(
+            format!("{:?}", bag),
+            "Bag { handler_count: 0, indices: [] }"
+        );
+    }
+
+    #[test]
+    fn call_reporting() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let outcome = bag.call_reporting(|handler| handler());
+        assert!(!outcome.fired());
+        assert_eq!(outcome.handlers_invoked, 0);
+
+        bag.add_detached(Arc::new(|| {}));
+        bag.add_detached(Arc::new(|| {}));
+
+        let outcome = bag.call_reporting(|handler| handler());
+        assert!(outcome.fired());
+        assert!(matches!(
+            outcome,
+            DispatchOutcome {
+                handlers_invoked: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn deferred_removal() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::with_deferred_removal();
+        let removed = Arc::new(AtomicUsize::new(0));
+        {
+            let removed = Arc::clone(&removed);
+            bag.on_change(move |kind, _index| {
+                if kind == ChangeKind::Removed {
+                    removed.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+        let handler_id = Mutex::new(Some(bag.add(Arc::new(|| {}))));
+        bag.add_detached(Arc::new(|| {}));
+
+        // Bag is locked while predicate is running, handler ID is dropped without deadlocking
+        bag.retain(|_| {
+            handler_id.lock().take();
+            true
+        });
+        assert_eq!(bag.len(), 2);
+        assert_eq!(removed.load(Ordering::SeqCst), 0);
+
+        assert_eq!(bag.call_simple(), 1);
+        assert_eq!(bag.len(), 1);
+        assert_eq!(removed.load(Ordering::SeqCst), 1);
+
+        // Adding handler also removes handlers queued for removal
+        let handler_id = bag.add(Arc::new(|| {}));
+        handler_id.remove_now();
+        assert_eq!(bag.len(), 2);
+        bag.add_detached(Arc::new(|| {}));
+        assert_eq!(bag.len(), 2);
+        assert_eq!(removed.load(Ordering::SeqCst), 2);
+
+        // Explicit removal is immediate
+        let handler_id = bag.add(Arc::new(|| {}));
+        assert!(bag.remove(&handler_id));
+        assert_eq!(bag.len(), 2);
+
+        // Handler with the highest priority is queued for removal, methods that call, take or
+        // copy handlers must not observe it
+        let queue_removal = || {
+            bag.add_with_priority(Arc::new(|| panic!("Removed handler must not be called")), 1)
+                .remove_now();
+            assert_eq!(bag.len(), 3);
+        };
+
+        queue_removal();
+        assert_eq!(bag.call_first(|handler| handler()), Some(()));
+        assert_eq!(bag.len(), 2);
+
+        queue_removal();
+        assert_eq!(bag.call_rotating(|handler| handler()), 2);
+
+        queue_removal();
+        assert_eq!(bag.call_locked(|handler| handler()), 2);
+
+        queue_removal();
+        assert_eq!(bag.call_nonblocking(|handler| handler()), Ok(2));
+        // Handler is skipped, but not removed, since that would require waiting for the lock
+        assert_eq!(bag.len(), 3);
+        assert_eq!(bag.call_simple(), 2);
+
+        #[cfg(feature = "parking_lot")]
+        {
+            use std::time::Duration;
+
+            queue_removal();
+            assert_eq!(
+                bag.call_timeout(|handler| handler(), Duration::from_millis(10)),
+                Ok(2)
+            );
+            assert_eq!(bag.len(), 3);
+            assert_eq!(bag.call_simple(), 2);
+        }
+
+        queue_removal();
+        let mut checked = 0;
+        bag.retain(|_handler| {
+            checked += 1;
+            true
+        });
+        assert_eq!(checked, 2);
+
+        queue_removal();
+        let handler_ids = bag.add_all(vec![
+            Arc::new(|| {}) as Arc<dyn Fn() + Send + Sync + 'static>
+        ]);
+        assert_eq!(bag.len(), 3);
+        assert!(bag.remove(&handler_ids[0]));
+
+        queue_removal();
+        let handler_id = bag.try_add(Arc::new(|| {})).unwrap();
+        assert_eq!(bag.len(), 3);
+        assert!(bag.remove(&handler_id));
+
+        // Queued handler is no longer in the bag when uniqueness is checked
+        let handler: Arc<dyn Fn() + Send + Sync + 'static> = Arc::new(|| {});
+        bag.add_unique(Arc::clone(&handler)).unwrap().remove_now();
+        assert_eq!(bag.len(), 3);
+        let handler_id = bag.add_unique(handler).unwrap();
+        assert_eq!(bag.len(), 3);
+        assert!(bag.remove(&handler_id));
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        bag.add(Arc::new(|| panic!("Removed handler must not be called")))
+            .remove_now();
+        assert_eq!(bag.len(), 4);
+        let handler_id = bag.replace(&handler_id, Arc::new(|| {}));
+        assert_eq!(bag.len(), 3);
+        assert!(bag.remove(&handler_id));
+
+        queue_removal();
+        let fork = bag.fork();
+        assert_eq!(fork.len(), 2);
+        assert_eq!(fork.call_simple(), 2);
+
+        queue_removal();
+        let handlers = bag.drain().collect::<Vec<_>>();
+        assert_eq!(handlers.len(), 2);
+        bag.add_all(handlers)
+            .into_iter()
+            .for_each(|handler_id| handler_id.detach());
+
+        queue_removal();
+        assert_eq!(bag.call_once_simple(), 2);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn drain() {
+        let bag =
+            Bag::<Arc<dyn Fn(&mut Vec<usize>) + Send + Sync + 'static>, Vec<usize>>::default();
+        let other_bag =
+            Bag::<Arc<dyn Fn(&mut Vec<usize>) + Send + Sync + 'static>, Vec<usize>>::default();
+
+        let handler_id = bag.add(Arc::new(|called| called.push(0)));
+        bag.add_detached(Arc::new(|called| called.push(1)));
+        let _handler_id = bag.add_with_priority(Arc::new(|called| called.push(2)), 1);
+
+        let handlers = bag.drain();
+        assert_eq!(handlers.len(), 3);
+        assert!(bag.is_empty());
+        for handler in handlers {
+            other_bag.add_detached(handler);
+        }
+
+        // Handler ID of drained handler does nothing, even if a new handler is added to the bag
+        bag.add_detached(Arc::new(|called| called.push(3)));
+        drop(handler_id);
+        assert_eq!(bag.len(), 1);
+        assert_eq!(other_bag.len(), 3);
+
+        let mut called = Vec::new();
+        other_bag.call_simple_mut(&mut called);
+        assert_eq!(called, vec![2, 0, 1]);
+
+        assert_eq!(bag.drain().len(), 1);
+        assert_eq!(bag.drain().len(), 0);
+    }
+
+    #[test]
+    fn owns() {
+        use event_listener_primitives::HandlerId;
+
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let other_bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+
+        let handler_id = bag.add(Arc::new(|| {}));
+        let other_handler_id = other_bag.add(Arc::new(|| {}));
+
+        assert!(bag.owns(&handler_id));
+        assert!(bag.clone().owns(&handler_id));
+        assert!(!bag.owns(&other_handler_id));
+        assert!(other_bag.owns(&other_handler_id));
+
+        handler_id.remove_now();
+        assert!(bag.owns(&handler_id));
+
+        let merged = HandlerId::merge(vec![handler_id, other_handler_id]);
+        assert!(!bag.owns(&merged));
+        assert!(!other_bag.owns(&merged));
+    }
+
+    #[test]
+    fn shared_context_argument() {
+        struct Context {
+            multiplier: usize,
+        }
+
+        // Context is passed as the first argument instead of being captured by every handler
+        let bag =
+            Bag::<Arc<dyn Fn(&Context, &usize) + Send + Sync + 'static>, Context, usize>::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+        for _ in 0..2 {
+            let sum = Arc::clone(&sum);
+            bag.add_detached(Arc::new(move |context, value| {
+                sum.fetch_add(context.multiplier * value, Ordering::SeqCst);
+            }));
+        }
+
+        assert_eq!(bag.call_simple(&Context { multiplier: 1 }, &3), 2);
+        assert_eq!(sum.load(Ordering::SeqCst), 6);
+
+        // Handlers see updated context on each call
+        assert_eq!(bag.call_simple(&Context { multiplier: 10 }, &1), 2);
+        assert_eq!(sum.load(Ordering::SeqCst), 26);
+    }
+
+    #[test]
+    fn call_with_mode() {
+        let bag =
+            Bag::<Arc<dyn Fn(&mut Vec<usize>) + Send + Sync + 'static>, Vec<usize>>::default();
+        bag.add_detached(Arc::new(|called| called.push(0)));
+        bag.add_detached(Arc::new(|called| called.push(1)));
+        let _handler_id = bag.add_with_priority(Arc::new(|called| called.push(2)), 1);
+
+        assert_eq!(DispatchMode::default(), DispatchMode::Snapshot);
+        for mode in [DispatchMode::Snapshot, DispatchMode::Live] {
+            let called = Mutex::new(Vec::new());
+            assert_eq!(
+                bag.call_with_mode(mode, |handler| handler(&mut called.lock())),
+                3
+            );
+            assert_eq!(called.into_inner(), vec![2, 0, 1]);
+        }
+
+        // Snapshot mode allows handlers to access the bag
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        {
+            let weak_bag = bag.downgrade();
+            bag.add_detached(Arc::new(move || {
+                if let Some(bag) = weak_bag.upgrade() {
+                    bag.add_detached(Arc::new(|| {}));
+                }
+            }));
+        }
+        assert_eq!(
+            bag.call_with_mode(DispatchMode::Snapshot, |handler| handler()),
+            1
+        );
+        assert_eq!(bag.len(), 2);
+    }
+
+    #[test]
+    fn set_middleware() {
+        let bag = Bag::<Arc<dyn Fn(&Mutex<Vec<String>>) + Send + Sync + 'static>, _>::default();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        bag.add_detached(Arc::new(|log: &Mutex<Vec<String>>| {
+            log.lock().push("first".to_string())
+        }));
+        let handler_id = bag.add(Arc::new(|log: &Mutex<Vec<String>>| {
+            log.lock().push("second".to_string())
+        }));
+
+        {
+            let log = Arc::clone(&log);
+            bag.set_middleware(move |index, handler| {
+                log.lock().push(format!("before {}", index));
+                handler();
+                log.lock().push(format!("after {}", index));
+            });
+        }
+        assert_eq!(bag.call_simple(&log), 2);
+        assert_eq!(
+            *log.lock(),
+            vec!["before 0", "first", "after 0", "before 1", "second", "after 1"]
+        );
+
+        // Middleware can skip handlers
+        log.lock().clear();
+        bag.set_middleware(move |index, handler| {
+            if index != handler_id.index().unwrap() {
+                handler();
+            }
+        });
+        assert_eq!(bag.call_simple(&log), 2);
+        assert_eq!(*log.lock(), vec!["first"]);
+    }
+
+    #[test]
+    fn set_middleware_call_modes() {
+        use event_listener_primitives::Callable;
+
+        let bag = Bag::<Arc<dyn Fn() -> Result<(), ()> + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        for _ in 0..2 {
+            let calls = Arc::clone(&calls);
+            bag.add_detached(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }));
+        }
+        let wrapped = Arc::new(AtomicUsize::new(0));
+        {
+            let wrapped = Arc::clone(&wrapped);
+            bag.set_middleware(move |_index, handler| {
+                wrapped.fetch_add(1, Ordering::SeqCst);
+                handler();
+            });
+        }
+        let assert_wrapped = |expected: usize| {
+            assert_eq!(wrapped.swap(0, Ordering::SeqCst), expected);
+            assert_eq!(calls.swap(0, Ordering::SeqCst), expected);
+        };
+
+        bag.call_locked(|handler| handler().unwrap());
+        assert_wrapped(2);
+        bag.call_with_mode(DispatchMode::Live, |handler| handler().unwrap());
+        assert_wrapped(2);
+        bag.call_nonblocking(|handler| handler().unwrap()).unwrap();
+        assert_wrapped(2);
+        #[cfg(feature = "parking_lot")]
+        {
+            bag.call_timeout(
+                |handler| handler().unwrap(),
+                std::time::Duration::from_secs(1),
+            )
+            .unwrap();
+            assert_wrapped(2);
+        }
+        bag.call_first(|handler| handler());
+        assert_wrapped(1);
+        bag.call_rotating(|handler| handler().unwrap());
+        assert_wrapped(2);
+        bag.call_shuffled(42, |handler| handler().unwrap());
+        assert_wrapped(2);
+        bag.call_retaining(|handler| handler().is_ok());
+        assert_wrapped(2);
+        #[cfg(feature = "std")]
+        {
+            bag.call_with_deadline(
+                |handler| handler().unwrap(),
+                std::time::Duration::from_secs(60),
+            );
+            assert_wrapped(2);
+        }
+        bag.call_on(|task| task(), |handler| handler().unwrap());
+        assert_wrapped(2);
+        bag.call_until(|handler| match handler() {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(()) => ControlFlow::<()>::Break(()),
+        });
+        assert_wrapped(2);
+        bag.try_call_stop(|handler| handler()).unwrap();
+        assert_wrapped(2);
+        #[cfg(feature = "rayon")]
+        {
+            bag.call_parallel(|handler| handler().unwrap());
+            assert_wrapped(2);
+        }
+        Callable::call(&bag, |handler| handler().unwrap());
+        assert_wrapped(2);
+        bag.call_once(|handler| handler().unwrap());
+        assert_wrapped(2);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn set_middleware_skipping_handlers() {
+        let bag = Bag::<Arc<dyn Fn() -> Result<(), ()> + Send + Sync + 'static>>::default();
+        bag.add_detached(Arc::new(|| Err(())));
+        bag.set_middleware(|_index, _handler| {});
+
+        // Skipped handler doesn't stop dispatch, isn't removed and produces no result
+        assert_eq!(bag.try_call_stop(|handler| handler()), Ok(()));
+        assert_eq!(
+            bag.call_until(|handler| match handler() {
+                Ok(()) => ControlFlow::Continue(()),
+                Err(()) => ControlFlow::Break(()),
+            }),
+            None
+        );
+        assert_eq!(bag.call_first(|handler| handler()), None);
+        assert_eq!(bag.call_retaining(|handler| handler().is_ok()), 1);
+        assert_eq!(bag.len(), 1);
+    }
+
+    #[test]
+    fn drop_order() {
+        struct Resource {
+            id: usize,
+            dropped: Arc<Mutex<Vec<usize>>>,
+        }
+
+        impl Drop for Resource {
+            fn drop(&mut self) {
+                self.dropped.lock().push(self.id);
+            }
+        }
+
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let add_handlers = |bag: &Bag<Arc<dyn Fn() + Send + Sync + 'static>>| {
+            for id in 0..3 {
+                let resource = Resource {
+                    id,
+                    dropped: Arc::clone(&dropped),
+                };
+                bag.add_detached(Arc::new(move || {
+                    let _ = &resource;
+                }));
+            }
+            let resource = Resource {
+                id: 3,
+                dropped: Arc::clone(&dropped),
+            };
+            bag.add_with_priority(
+                Arc::new(move || {
+                    let _ = &resource;
+                }),
+                1,
+            )
+            .detach();
+        };
+
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        add_handlers(&bag);
+        bag.clear();
+        assert_eq!(*dropped.lock(), vec![3, 2, 1, 0]);
+
+        dropped.lock().clear();
+        add_handlers(&bag);
+        let bag_clone = bag.clone();
+        drop(bag);
+        assert!(dropped.lock().is_empty());
+        drop(bag_clone);
+        assert_eq!(*dropped.lock(), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn call_with_deadline() {
+        use std::time::Duration;
+
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        for _ in 0..3 {
+            let calls = Arc::clone(&calls);
+            bag.add_detached(Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+            }));
+        }
+
+        let outcome = bag.call_with_deadline(|handler| handler(), Duration::from_secs(60));
+        assert_eq!(outcome.handlers_invoked, 3);
+        assert_eq!(outcome.handlers_skipped, 0);
+
+        // First handler exhausts the budget
+        let outcome = bag.call_with_deadline(|handler| handler(), Duration::from_millis(10));
+        assert_eq!(outcome.handlers_invoked, 1);
+        assert_eq!(outcome.handlers_skipped, 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        let outcome = bag.call_with_deadline(|handler| handler(), Duration::ZERO);
+        assert!(!outcome.fired());
+        assert_eq!(outcome.handlers_skipped, 3);
+    }
+
+    #[test]
+    fn add_existing_arc() {
+        // Handlers are stored as is, so existing `Arc` is added without additional allocation
+        let handler: Arc<dyn Fn() + Send + Sync + 'static> = Arc::new(|| {});
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let _handler_id = bag.add(Arc::clone(&handler));
+
+        let snapshot = bag.snapshot();
+        assert!(Arc::ptr_eq(&snapshot[0], &handler));
+        assert_eq!(Arc::strong_count(&handler), 3);
+    }
+
+    #[test]
+    fn call_on() {
+        let bag = Bag::<Arc<dyn Fn(&usize) + Send + Sync + 'static>, usize>::default();
+        let sum = Arc::new(AtomicUsize::new(0));
+        for _ in 0..2 {
+            let sum = Arc::clone(&sum);
+            bag.add_detached(Arc::new(move |value| {
+                sum.fetch_add(*value, Ordering::SeqCst);
+            }));
+        }
+
+        let mut task = None;
+        assert_eq!(
+            bag.call_on(|spawned| task = Some(spawned), |handler| handler(&2)),
+            2
+        );
+        // Nothing is called until task runs, handlers are collected already though
+        assert_eq!(sum.load(Ordering::SeqCst), 0);
+        bag.clear();
+        task.unwrap()();
+        assert_eq!(sum.load(Ordering::SeqCst), 4);
+
+        let handler_id = {
+            let sum = Arc::clone(&sum);
+            bag.add(Arc::new(move |value| {
+                sum.fetch_add(*value, Ordering::SeqCst);
+            }))
+        };
+        let mut worker = None;
+        bag.call_on(
+            |spawned| worker = Some(thread::spawn(spawned)),
+            |handler| handler(&1),
+        );
+        drop(handler_id);
+        worker.unwrap().join().unwrap();
+        assert_eq!(sum.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn unsized_arguments() {
+        use std::fmt::Display;
+
+        // Arguments of `call_simple()` must be `Sized`, so `call()` is used for `&dyn Trait`
+        let bag = Bag::<Arc<dyn Fn(&dyn Display) -> String + Send + Sync + 'static>>::default();
+        bag.add_detached(Arc::new(|value| format!("<{}>", value)));
+
+        let mut results = Vec::new();
+        let value: &dyn Display = &1;
+        assert_eq!(bag.call(|handler| results.push(handler(value))), 1);
+        assert_eq!(bag.call(|handler| results.push(handler(&"a"))), 1);
+        assert_eq!(results, vec!["<1>", "<a>"]);
+    }
+
+    #[test]
+    fn remove_many() {
+        let bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let other_bag = Bag::<Arc<dyn Fn() + Send + Sync + 'static>>::default();
+        let removed = Arc::new(AtomicUsize::new(0));
+        {
+            let removed = Arc::clone(&removed);
+            bag.on_change(move |kind, _index| {
+                if kind == ChangeKind::Removed {
+                    removed.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        }
+
+        let mut handler_ids = (0..3).map(|_| bag.add(Arc::new(|| {}))).collect::<Vec<_>>();
+        let kept = handler_ids.pop().unwrap();
+        handler_ids.push(other_bag.add(Arc::new(|| {})));
+
+        assert_eq!(bag.remove_many(&handler_ids), 2);
+        assert_eq!(bag.len(), 1);
+        assert_eq!(removed.load(Ordering::SeqCst), 2);
+        assert!(!handler_ids[0].is_attached());
+        assert!(handler_ids[2].is_attached());
+        assert_eq!(bag.remove_many(&handler_ids), 0);
+
+        drop(handler_ids);
+        assert_eq!(bag.len(), 1);
+        assert!(other_bag.is_empty());
+        drop(kept);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn call_shuffled() {
+        let bag = Bag::<usize>::default();
+        for handler in 0..8_usize {
+            bag.add_detached(handler);
+        }
+
+        let order = |seed| {
+            let mut called = Vec::new();
+            assert_eq!(bag.call_shuffled(seed, |handler| called.push(*handler)), 8);
+            called
+        };
+
+        let shuffled = order(1);
+        assert_eq!(order(1), shuffled);
+        let mut sorted = shuffled.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..8).collect::<Vec<_>>());
+        // At least some seeds must result in order different from insertion order
+        assert!((0..4).any(|seed| order(seed) != sorted));
+
+        assert_eq!(Bag::<usize>::default().call_shuffled(0, |_| {}), 0);
+    }
+
+    #[test]
+    fn fork() {
+        let bag =
+            Bag::<Arc<dyn Fn(&mut Vec<usize>) + Send + Sync + 'static>, Vec<usize>>::default();
+        let handler_id = bag.add(Arc::new(|called| called.push(0)));
+        bag.add_detached(Arc::new(|called| called.push(1)));
+        bag.add_with_priority(Arc::new(|called| called.push(2)), 1)
+            .detach();
+
+        let fork = bag.fork();
+        assert!(fork.is_unique());
+        let mut called = Vec::new();
+        assert_eq!(fork.call_simple_mut(&mut called), 3);
+        assert_eq!(called, vec![2, 0, 1]);
+
+        // Changes don't propagate between bag and its fork
+        drop(handler_id);
+        fork.add_detached(Arc::new(|called| called.push(3)));
+        assert_eq!(bag.len(), 2);
+        assert_eq!(fork.len(), 4);
+
+        let mut called = Vec::new();
+        fork.call_simple_mut(&mut called);
+        assert_eq!(called, vec![2, 0, 1, 3]);
+        let mut called = Vec::new();
+        bag.call_simple_mut(&mut called);
+        assert_eq!(called, vec![2, 1]);
+    }
+
+    #[test]
+    fn retain_handler_owning_handler_id() {
+        let bag = Bag::<Arc<dyn Fn() -> bool + Send + Sync + 'static>>::default();
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        {
+            let removed = Arc::clone(&removed);
+            bag.on_change(move |kind, index| {
+                if kind == ChangeKind::Removed {
+                    removed.lock().push(index);
+                }
+            });
+        }
+        let handler_id = bag.add(Arc::new(|| true));
+        bag.add(Arc::new(move || {
+            let _ = &handler_id;
+            false
+        }))
+        .detach();
+
+        // Pruned handler is dropped after lock is released, so handler ID it owns can remove the
+        // other handler without deadlocking
+        bag.retain(|handler| handler());
+        assert!(bag.is_empty());
+
+        let mut removed = removed.lock().clone();
+        removed.sort_unstable();
+        assert_eq!(removed, vec![0, 1]);
+    }
 }